@@ -1,3 +1,5 @@
+use crate::Board;
+
 pub enum Click {
     LeftUp,
     LeftDown,
@@ -12,6 +14,24 @@ pub enum InputState {
     Chord,
 }
 impl InputState {
+    /// The closed squares that show as pushed in with the mouse over
+    /// `cursor`: the square itself while the left button is held, and its
+    /// whole neighbourhood while chording.
+    pub fn pressed(&self, board: &Board, cursor: Option<(usize, usize)>) -> Vec<(usize, usize)> {
+        let Some(p) = cursor else { return vec![] };
+        let mut squares = match self {
+            InputState::Left => vec![p],
+            InputState::Chord => {
+                let mut squares: Vec<_> = board.get_neighborhood(p).collect();
+                squares.push(p);
+                squares
+            },
+            _ => vec![],
+        };
+        squares.retain(|&q| !board[q].open && !board[q].is_flagged());
+        squares
+    }
+
     pub fn transition (&mut self, input_action: InputAction, (x, y): (usize, usize)) -> Action {
         match input_action {
             InputAction::LeftUp => {
@@ -71,6 +91,7 @@ impl InputState {
     }
 }
 
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub enum InputAction {
     LeftUp,
     LeftDown,
@@ -88,3 +109,89 @@ pub enum Action {
     None,
 }
 
+/// Something done with the keyboard instead of the mouse
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum KeyAction {
+    Move (isize, isize),
+    Open,
+    Flag,
+    Chord,
+    Restart,
+}
+
+/// The square picked with the keyboard. It moves on its own, so it
+/// doesn't disturb the mouse's [`InputState`].
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct KeyCursor {
+    pub square: (usize, usize),
+}
+impl KeyCursor {
+    /// Moves the cursor, or acts on the square under it. Opening a square
+    /// that is already open chords it.
+    pub fn transition(&mut self, key_action: KeyAction, board: &Board) -> Action {
+        // the board may have shrunk since the cursor last moved
+        let (x, y) = self.square;
+        let (x, y) = (x.min(board.width() - 1), y.min(board.height() - 1));
+        self.square = (x, y);
+        match key_action {
+            KeyAction::Move(dx, dy) => {
+                self.square = (
+                    x.saturating_add_signed(dx).min(board.width() - 1),
+                    y.saturating_add_signed(dy).min(board.height() - 1),
+                );
+                Action::None
+            },
+            KeyAction::Open if board[(x, y)].open => Action::Chord(x, y),
+            KeyAction::Open => Action::Open(x, y),
+            KeyAction::Flag => Action::Flag(x, y),
+            KeyAction::Chord => Action::Chord(x, y),
+            KeyAction::Restart => Action::Btn,
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    pub fn pressed() {
+        let mut board = Board::with_mines(4, 4, &[(3, 3)]);
+        board[(0, 0)].flags = 1;
+        board[(2, 2)].open = true;
+
+        assert!(InputState::None.pressed(&board, Some((1, 1))).is_empty());
+        assert!(InputState::Right.pressed(&board, Some((1, 1))).is_empty());
+        assert!(InputState::Left.pressed(&board, None).is_empty());
+        assert_eq!(InputState::Left.pressed(&board, Some((1, 1))), vec![(1, 1)]);
+        assert!(InputState::Left.pressed(&board, Some((2, 2))).is_empty());
+
+        let mut chord = InputState::Chord.pressed(&board, Some((1, 1)));
+        chord.sort();
+        assert_eq!(chord, vec![
+            (0, 1), (0, 2), (1, 0), (1, 1), (1, 2), (2, 0), (2, 1),
+        ]);
+    }
+
+    #[test]
+    pub fn key_cursor() {
+        let mut board = Board::with_mines(3, 2, &[(2, 1)]);
+        board[(1, 0)].open = true;
+        let mut cursor = KeyCursor::default();
+
+        assert!(matches!(cursor.transition(KeyAction::Move(-1, 1), &board), Action::None));
+        assert_eq!(cursor.square, (0, 1));
+        cursor.transition(KeyAction::Move(5, 5), &board);
+        assert_eq!(cursor.square, (2, 1));
+        assert!(matches!(cursor.transition(KeyAction::Flag, &board), Action::Flag(2, 1)));
+
+        cursor.square = (1, 0);
+        assert!(matches!(cursor.transition(KeyAction::Open, &board), Action::Chord(1, 0)));
+        cursor.square = (0, 0);
+        assert!(matches!(cursor.transition(KeyAction::Open, &board), Action::Open(0, 0)));
+
+        // a cursor left off a smaller board comes back onto it
+        cursor.square = (8, 8);
+        assert!(matches!(cursor.transition(KeyAction::Chord, &board), Action::Chord(2, 1)));
+    }
+}
@@ -0,0 +1,300 @@
+//! Plays in a terminal, for when there is no display to open a window on.
+
+use std::io::{ self, Stdout, Write };
+use std::path::PathBuf;
+use std::time::Duration;
+
+use crossterm::{ cursor, execute, queue, terminal };
+use crossterm::event::{ self, Event, KeyCode, KeyEvent, KeyEventKind, KeyModifiers };
+use crossterm::style::{ Color, Print, PrintStyledContent, Stylize };
+use crossterm::terminal::{ Clear, ClearType };
+
+use rustsweeper::*;
+
+/// Columns taken up by each square
+const CELL_WIDTH: usize = 2;
+/// Rows above the board, for the counters and the face
+const HEADER_HEIGHT: usize = 1;
+/// Rows below the board, for messages and the keys
+const FOOTER_HEIGHT: usize = 2;
+const KEYS: &str = "arrows/hjkl move  space open  f flag  c chord  n new  1-3 size  q quit";
+/// How often to redraw for the timer when no keys are pressed
+const TICK: Duration = Duration::from_millis(250);
+
+/// Puts the terminal back the way it was, even after a panic.
+struct RawTerminal;
+impl RawTerminal {
+    fn enter(out: &mut Stdout) -> io::Result<Self> {
+        terminal::enable_raw_mode()?;
+        execute!(out, terminal::EnterAlternateScreen, cursor::Hide)?;
+        Ok(Self)
+    }
+}
+impl Drop for RawTerminal {
+    fn drop(&mut self) {
+        let _ = execute!(io::stdout(), cursor::Show, terminal::LeaveAlternateScreen);
+        let _ = terminal::disable_raw_mode();
+    }
+}
+
+/// The game and the part of it that fits on screen
+struct Tui {
+    game: Game,
+    recorder: Recorder,
+    /// Where replays of finished games go, if anywhere
+    record: Option<PathBuf>,
+    cursor: KeyCursor,
+    /// The square in the top left corner of the screen
+    scroll: (usize, usize),
+    /// The terminal size in columns and rows
+    size: (usize, usize),
+    /// A line about the last thing that happened
+    message: String,
+}
+impl Tui {
+    fn new(options: Options, (columns, rows): (u16, u16)) -> Self {
+        Self {
+            game: Game::new(options.settings),
+            recorder: Recorder::new(),
+            record: options.record,
+            cursor: KeyCursor::default(),
+            scroll: (0, 0),
+            size: (columns as usize, rows as usize),
+            message: String::new(),
+        }
+    }
+
+    /// How many squares across and down fit on screen.
+    fn visible(&self) -> (usize, usize) {
+        let (columns, rows) = self.size;
+        // hex boards push every other row half a cell over
+        let shift = (self.game.board().grid() == Grid::Hex) as usize;
+        (
+            (columns.saturating_sub(shift) / CELL_WIDTH).min(self.game.board().width()),
+            rows.saturating_sub(HEADER_HEIGHT + FOOTER_HEIGHT).min(self.game.board().height()),
+        )
+    }
+
+    /// Scrolls just far enough for the cursor to be on screen.
+    fn follow_cursor(&mut self) {
+        let (across, down) = self.visible();
+        let board = self.game.board();
+        for (cursor, scroll, visible, len) in [
+            (self.cursor.square.0, &mut self.scroll.0, across, board.width()),
+            (self.cursor.square.1, &mut self.scroll.1, down, board.height()),
+        ] {
+            *scroll = (*scroll).min(len - visible.max(1));
+            if cursor < *scroll {
+                *scroll = cursor;
+            } else if visible > 0 && cursor >= *scroll + visible {
+                *scroll = cursor + 1 - visible;
+            }
+        }
+    }
+
+    fn set_difficulty(&mut self, difficulty: Difficulty) {
+        self.game.set_difficulty(difficulty);
+        self.recorder.restart();
+        self.cursor = KeyCursor::default();
+        self.scroll = (0, 0);
+        self.message = format!("new {} game", difficulty);
+    }
+
+    /// The action for a key press. Keys that change the difficulty are
+    /// handled here.
+    fn key_action(&mut self, key: KeyEvent) -> Action {
+        let key_action = match key.code {
+            KeyCode::Char('q') | KeyCode::Esc => return Action::Quit,
+            KeyCode::Char('c') if key.modifiers.contains(KeyModifiers::CONTROL)
+                => return Action::Quit,
+
+            KeyCode::Char('1') => { self.set_difficulty(Difficulty::Beginner); return Action::None },
+            KeyCode::Char('2') => { self.set_difficulty(Difficulty::Intermediate); return Action::None },
+            KeyCode::Char('3') => { self.set_difficulty(Difficulty::Expert); return Action::None },
+
+            KeyCode::Left | KeyCode::Char('h') => KeyAction::Move(-1, 0),
+            KeyCode::Down | KeyCode::Char('j') => KeyAction::Move(0, 1),
+            KeyCode::Up | KeyCode::Char('k') => KeyAction::Move(0, -1),
+            KeyCode::Right | KeyCode::Char('l') => KeyAction::Move(1, 0),
+            KeyCode::Char(' ') | KeyCode::Enter => KeyAction::Open,
+            KeyCode::Char('f') => KeyAction::Flag,
+            KeyCode::Char('c') => KeyAction::Chord,
+            KeyCode::Char('n') => KeyAction::Restart,
+
+            _ => return Action::None,
+        };
+        self.recorder.record(ReplayInput::Key(key_action));
+        self.cursor.transition(key_action, self.game.board())
+    }
+
+    fn apply(&mut self, action: Action) {
+        for event in self.game.apply(action) {
+            match event {
+                GameEvent::Started { seed } => {
+                    self.message = seed.map(|seed| format!("seed: {}", seed)).unwrap_or_default();
+                },
+                GameEvent::NoGuessFailed(e) => {
+                    self.message = format!("no-guess generation failed: {}", e);
+                },
+                GameEvent::Lost { .. } | GameEvent::Won => {
+                    let metrics = metrics::Metrics::of(&self.game);
+                    self.message = match event {
+                        GameEvent::Won => format!("cleared, {}", metrics),
+                        _ => format!("boom, {}", metrics),
+                    };
+                    let replay = self.recorder.finish(&self.game);
+                    if let Some(dir) = &self.record {
+                        match replay.save_in(dir) {
+                            Ok(path) => self.message += &format!(", replay in {}", path.display()),
+                            Err(e) => self.message = format!("can't save the replay: {}", e),
+                        }
+                    }
+                },
+                GameEvent::Reset => {
+                    self.message.clear();
+                    self.recorder.restart();
+                },
+                _ => {},
+            }
+        }
+        self.follow_cursor();
+    }
+
+    fn resize(&mut self, columns: u16, rows: u16) {
+        self.size = (columns as usize, rows as usize);
+        self.follow_cursor();
+    }
+
+    fn draw(&self, out: &mut impl Write) -> io::Result<()> {
+        let (across, down) = self.visible();
+        if across == 0 || down == 0 {
+            queue!(
+                out,
+                Clear(ClearType::All),
+                cursor::MoveTo(0, 0),
+                Print("terminal too small"),
+            )?;
+            return out.flush();
+        }
+
+        let board = self.game.board();
+        let width = across * CELL_WIDTH;
+        let face = match self.game.state() {
+            GameState::Win => "B)",
+            GameState::Lose => "X(",
+            _ => ":)",
+        };
+        let seconds = self.game.timer().elapsed().as_secs();
+        let mines = format!("{:03}", self.game.mines_left());
+        let timer = format!("{:03}", seconds.min(999));
+        let gap = width.saturating_sub(mines.len() + face.len() + timer.len());
+        queue!(
+            out,
+            cursor::MoveTo(0, 0),
+            PrintStyledContent(mines.red()),
+            Print(" ".repeat(gap/2)),
+            PrintStyledContent(face.yellow()),
+            Print(" ".repeat(gap - gap/2)),
+            PrintStyledContent(timer.red()),
+            Clear(ClearType::UntilNewLine),
+        )?;
+
+        let lost = matches!(self.game.state(), GameState::Lose);
+        for row in 0..down {
+            queue!(out, cursor::MoveTo(0, (HEADER_HEIGHT + row) as u16))?;
+            if board.grid() == Grid::Hex && (self.scroll.1 + row) % 2 == 1 {
+                queue!(out, Print(" "))?;
+            }
+            for column in 0..across {
+                let p = (self.scroll.0 + column, self.scroll.1 + row);
+                let cell = &board[p];
+                let number = cell.num.to_string();
+                // with room for how many there are when there are several
+                let count = |c: char, n: u8| match n {
+                    1 => c.to_string(),
+                    n => format!("{}{}", c, n),
+                };
+                let (text, color) = match cell {
+                    c if c.open && c.is_mine() => (count('*', c.mines), Color::White),
+                    c if c.open && c.num == 0 => (" ".to_string(), Color::Reset),
+                    // a cell is just wide enough for two digits
+                    c if c.open => (number, number_color(c.num)),
+                    c if c.flags > c.mines && lost => ("X".to_string(), Color::Red),
+                    c if c.is_flagged() => (count('F', c.flags), Color::Red),
+                    c if lost && c.is_mine() => (count('*', c.mines), Color::Reset),
+                    _ => (".".to_string(), Color::DarkGrey),
+                };
+                let mut styled = format!("{:<1$}", text, CELL_WIDTH).with(color);
+                if cell.open && cell.is_mine() {
+                    styled = styled.on_red();
+                }
+                if p == self.cursor.square {
+                    styled = styled.reverse();
+                }
+                queue!(out, PrintStyledContent(styled))?;
+            }
+            queue!(out, Clear(ClearType::UntilNewLine))?;
+        }
+
+        let columns = self.size.0;
+        let message: String = self.message.chars().take(columns).collect();
+        let keys: String = KEYS.chars().take(columns).collect();
+        queue!(
+            out,
+            cursor::MoveTo(0, (HEADER_HEIGHT + down) as u16),
+            Print(message),
+            Clear(ClearType::UntilNewLine),
+            cursor::MoveToNextLine(1),
+            PrintStyledContent(keys.dark_grey()),
+            Clear(ClearType::FromCursorDown),
+        )?;
+        out.flush()
+    }
+}
+
+/// The colour of a number, the same as in the classic game.
+fn number_color(num: u8) -> Color {
+    match num {
+        1 => Color::Blue,
+        2 => Color::Green,
+        3 => Color::Red,
+        4 => Color::DarkBlue,
+        5 => Color::DarkRed,
+        6 => Color::Cyan,
+        7 => Color::Magenta,
+        _ => Color::Grey,
+    }
+}
+
+fn main() -> Result<(), String> {
+    let options = Options::from_args(std::env::args().skip(1))?;
+    if options.replay.is_some() {
+        return Err("--replay only plays back in the window, not in the terminal".to_string());
+    }
+    run(options).map_err(|e| e.to_string())
+}
+
+fn run(options: Options) -> io::Result<()> {
+    let mut out = io::stdout();
+    let _raw = RawTerminal::enter(&mut out)?;
+    let mut tui = Tui::new(options, terminal::size()?);
+
+    loop {
+        tui.draw(&mut out)?;
+        if !event::poll(TICK)? {
+            continue;
+        }
+        let action = match event::read()? {
+            Event::Key(key) if key.kind == KeyEventKind::Press => tui.key_action(key),
+            Event::Resize(columns, rows) => {
+                tui.resize(columns, rows);
+                Action::None
+            },
+            _ => Action::None,
+        };
+        if let Action::Quit = action {
+            return Ok(());
+        }
+        tui.apply(action);
+    }
+}
@@ -1,8 +1,11 @@
 use std::ops::{ Index, IndexMut };
 use std::default::Default;
+use std::fmt;
 
+use rand::{ Rng, SeedableRng };
 use rand::seq::SliceRandom;
 use rand::thread_rng;
+use rand_chacha::ChaCha8Rng;
 
 pub const BOARD_X: i32 = 0;
 pub const BOARD_Y: i32 = 26;
@@ -11,44 +14,573 @@ pub const SQ_U32: u32 = SQ_SIZE as u32;
 pub const SQ_I32: i32 = SQ_SIZE as i32; 
 
 /// A single unit of the Minesweeper board
-#[derive(Debug, Clone, Copy)]
+#[derive(Debug, Clone, Copy, Default)]
 pub struct Cell {
-    /// Whether or not this square has a mine
-    pub mine: bool,
-    /// The number on this mine
+    /// How many mines are on this square
+    pub mines: u8,
+    /// The number on this square: how many mines are around it
     pub num: u8,
     /// Whether the square is open
     pub open: bool,
-    /// Whether the square has a flag on it
-    pub flag: bool,
+    /// How many flags are on the square
+    pub flags: u8,
 }
-impl Default for Cell {
-    fn default() -> Self { 
-        Self {
-            mine: false,
-            num: 0,
-            open: false,
-            flag: false,
+impl Cell {
+    /// The most mines a square can hold, though big neighbourhoods allow
+    /// fewer, see [`Topology::max_mines_per_square`].
+    pub const MAX_MINES: u8 = 5;
+    /// The biggest number a square can show, so it always fits in two digits
+    pub const MAX_NUM: u8 = 99;
+
+    /// Whether there is at least one mine on the square.
+    pub fn is_mine(&self) -> bool {
+        self.mines > 0
+    }
+
+    /// Whether there is at least one flag on the square.
+    pub fn is_flagged(&self) -> bool {
+        self.flags > 0
+    }
+}
+
+/// A Minesweeper board of any shape.
+///
+/// A board says which squares it has and which of them are next to each
+/// other. Opening, chording, placing mines and numbering are written once
+/// here in terms of that.
+pub trait MineBoard {
+    /// How a square is addressed
+    type Square: Copy + PartialEq;
+
+    /// Every square, in the same order every time. The squares don't
+    /// borrow the board, so it can be changed while going through them.
+    fn squares(&self) -> impl Iterator<Item=Self::Square> + use<Self>;
+
+    /// The squares next to `p`, which don't borrow the board either.
+    fn neighbors(&self, p: Self::Square) -> impl Iterator<Item=Self::Square> + use<Self>;
+
+    fn cell(&self, p: Self::Square) -> &Cell;
+
+    fn cell_mut(&mut self, p: Self::Square) -> &mut Cell;
+
+    /// The most mines one square can hold, and so the most flags that can
+    /// go on it.
+    fn mines_per_square(&self) -> u8 {
+        1
+    }
+
+    /// Clears out the board to the default Cell.
+    fn reset(&mut self) {
+        for p in self.squares() {
+            *self.cell_mut(p) = Cell::default();
+        }
+    }
+
+    /// Opens a square. Returns whether or not it loses the game.
+    fn open(&mut self, p: Self::Square) -> bool {
+        let cell = *self.cell(p);
+        if cell.open || cell.is_flagged() {
+            return false;
+        }
+        self.cell_mut(p).open = true;
+        if cell.num == 0 && !cell.is_mine() {
+            for neighbor in self.neighbors(p) {
+                if !(self.cell(neighbor).open || self.cell(neighbor).is_mine()) {
+                    self.open(neighbor);
+                }
+            }
+        }
+        cell.is_mine()
+    }
+
+    /// Chords. Returns whether or not it loses the game
+    fn chord(&mut self, p: Self::Square) -> bool {
+        let cell = *self.cell(p);
+        if cell.is_mine() || !cell.open {
+            return false;
+        }
+        let flags: usize = self.neighbors(p).map(|q| self.cell(q).flags as usize).sum();
+        if cell.num as usize == flags {
+            for neighbor in self.neighbors(p) {
+                if self.open(neighbor) {
+                    return true
+                }
+            }
+        }
+        false
+    }
+
+    /// The number of mines on all the squares.
+    fn num_mines(&self) -> usize {
+        self.squares().map(|p| self.cell(p).mines as usize).sum()
+    }
+
+    /// The number of flags on all the squares.
+    fn num_flags(&self) -> usize {
+        self.squares().map(|p| self.cell(p).flags as usize).sum()
+    }
+
+    /// The number of safe squares that have been opened.
+    fn num_opened(&self) -> usize {
+        self.squares()
+            .filter(|&p| self.cell(p).open && !self.cell(p).is_mine())
+            .count()
+    }
+
+    /// Whether every safe square has been opened.
+    fn is_won(&self) -> bool {
+        self.squares().all(|p| self.cell(p).open || self.cell(p).is_mine())
+    }
+
+    /// Puts as many flags on every mine as there are mines.
+    fn flag_mines(&mut self) {
+        for p in self.squares() {
+            let mines = self.cell(p).mines;
+            if mines > 0 {
+                self.cell_mut(p).flags = mines;
+            }
+        }
+    }
+
+    /// Puts one more flag on the closed square at `p`, or takes them all
+    /// off once it has as many as a square can hold mines. Returns how many
+    /// flags it has now.
+    fn cycle_flags(&mut self, p: Self::Square) -> u8 {
+        let cell = *self.cell(p);
+        if cell.open {
+            return cell.flags;
+        }
+        let flags = (cell.flags + 1) % (self.mines_per_square() + 1);
+        self.cell_mut(p).flags = flags;
+        flags
+    }
+
+    /// Shuffles `num_mines` mines into every square except `excluded`, no
+    /// more than [`MineBoard::mines_per_square`] to a square, and numbers
+    /// the squares.
+    fn place_mines<R: Rng + ?Sized>(
+        &mut self,
+        num_mines: usize,
+        excluded: &[Self::Square],
+        rng: &mut R,
+    ) where Self: Sized {
+        // generate mines, into as many places on each square as it can hold
+        let per_square = self.mines_per_square() as usize;
+        let mut mines = vec![false; (self.squares().count() - excluded.len())*per_square];
+        for m in mines.iter_mut().take(num_mines) {
+            *m = true;
+        }
+        mines.shuffle(rng);
+
+        // assign mines
+        let mut mines = mines.into_iter();
+        for p in self.squares() {
+            let count = if excluded.contains(&p) {
+                0
+            } else {
+                mines.by_ref().take(per_square).filter(|&m| m).count()
+            };
+            self.cell_mut(p).mines = count as u8;
+        }
+
+        self.compute_numbers();
+    }
+
+    /// Fills in the number on every square from the mines around it.
+    fn compute_numbers(&mut self) {
+        for p in self.squares() {
+            let num: usize = self.neighbors(p)
+                .map(|q| self.cell(q).mines as usize)
+                .sum();
+            self.cell_mut(p).num = num as u8;
+        }
+    }
+}
+
+/// Which edges of a board join up with the opposite edge, so that the
+/// squares along them have neighbours on the other side
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash)]
+pub struct Wrap {
+    /// The left and right edges are next to each other
+    pub horizontal: bool,
+    /// The top and bottom edges are next to each other
+    pub vertical: bool,
+}
+impl Wrap {
+    /// Whether any edge wraps around.
+    pub fn any(&self) -> bool {
+        self.horizontal || self.vertical
+    }
+}
+impl fmt::Display for Wrap {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let name = match (self.horizontal, self.vertical) {
+            (false, false) => "none",
+            (true, false) => "horizontal",
+            (false, true) => "vertical",
+            (true, true) => "both",
+        };
+        write!(f, "{}", name)
+    }
+}
+impl std::str::FromStr for Wrap {
+    type Err = ParseWrapError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let (horizontal, vertical) = match s {
+            "none" => (false, false),
+            "horizontal" => (true, false),
+            "vertical" => (false, true),
+            "both" => (true, true),
+            _ => return Err(ParseWrapError(s.to_string())),
+        };
+        Ok(Self { horizontal, vertical })
+    }
+}
+
+/// A wrap that isn't one of `none`, `horizontal`, `vertical` or `both`
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ParseWrapError(pub String);
+impl fmt::Display for ParseWrapError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{:?} is not a wrap, expected none, horizontal, vertical or both", self.0)
+    }
+}
+impl std::error::Error for ParseWrapError {}
+
+/// The shape of the squares, which decides which of them are next to each
+/// other
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash)]
+pub enum Grid {
+    /// Squares, each with up to eight neighbours
+    #[default]
+    Square,
+    /// Hexagons, each with up to six neighbours. Every other row, starting
+    /// with the second, is pushed half a hexagon to the right.
+    Hex,
+}
+impl Grid {
+    /// How far apart the tops of two rows are, in pixels
+    pub fn row_height(&self) -> usize {
+        match self {
+            Grid::Square => SQ_SIZE,
+            Grid::Hex => SQ_SIZE*3/4,
+        }
+    }
+
+    /// The width and height in pixels of `width` by `height` squares.
+    pub fn span(&self, width: usize, height: usize) -> (usize, usize) {
+        match self {
+            _ if width == 0 || height == 0 => (0, 0),
+            Grid::Square => (width*SQ_SIZE, height*SQ_SIZE),
+            Grid::Hex => (
+                width*SQ_SIZE + if height > 1 { SQ_SIZE/2 } else { 0 },
+                (height - 1)*self.row_height() + SQ_SIZE,
+            ),
+        }
+    }
+}
+impl fmt::Display for Grid {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Grid::Square => write!(f, "square"),
+            Grid::Hex => write!(f, "hex"),
+        }
+    }
+}
+impl std::str::FromStr for Grid {
+    type Err = ParseGridError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "square" => Ok(Grid::Square),
+            "hex" => Ok(Grid::Hex),
+            _ => Err(ParseGridError(s.to_string())),
+        }
+    }
+}
+
+/// A grid that isn't `square` or `hex`
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ParseGridError(pub String);
+impl fmt::Display for ParseGridError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{:?} is not a grid, expected square or hex", self.0)
+    }
+}
+impl std::error::Error for ParseGridError {}
+
+/// Which squares around a square are next to it, as offsets no more than
+/// [`Neighborhood::REACH`] away across or down.
+///
+/// Each offset is a bit, from the top left row by row, so a neighbourhood
+/// is cheap to copy and the offsets always come out in the same order.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct Neighborhood(u64);
+impl Neighborhood {
+    /// How far away the furthest neighbour can be, across or down
+    pub const REACH: i32 = 3;
+    const SIDE: i32 = 2*Self::REACH + 1;
+
+    /// The eight squares around, as in the classic game
+    pub const KING: Self = Self::within(1);
+    /// The four squares above, below and to either side
+    pub const CROSS: Self = Self(
+        Self::bit(0, -1) | Self::bit(-1, 0) | Self::bit(1, 0) | Self::bit(0, 1)
+    );
+    /// The eight squares a knight's move away
+    pub const KNIGHT: Self = Self(
+        Self::bit(-1, -2) | Self::bit(1, -2) | Self::bit(-2, -1) | Self::bit(2, -1)
+            | Self::bit(-2, 1) | Self::bit(2, 1) | Self::bit(-1, 2) | Self::bit(1, 2)
+    );
+    /// The twenty four squares up to two away
+    pub const RADIUS_2: Self = Self::within(2);
+
+    /// The six hexagons around one in an unshifted row, where the rows
+    /// either side are shifted half a hexagon to the right
+    const HEX: Self = Self(
+        Self::bit(-1, -1) | Self::bit(0, -1) | Self::bit(-1, 0) | Self::bit(1, 0)
+            | Self::bit(-1, 1) | Self::bit(0, 1)
+    );
+    /// The six hexagons around one in a shifted row
+    const HEX_SHIFTED: Self = Self(
+        Self::bit(0, -1) | Self::bit(1, -1) | Self::bit(-1, 0) | Self::bit(1, 0)
+            | Self::bit(0, 1) | Self::bit(1, 1)
+    );
+
+    const fn bit(dx: i32, dy: i32) -> u64 {
+        1 << ((dy + Self::REACH)*Self::SIDE + dx + Self::REACH)
+    }
+
+    /// Every square up to `reach` away across and down.
+    const fn within(reach: i32) -> Self {
+        let mut bits = 0;
+        let mut dy = -reach;
+        while dy <= reach {
+            let mut dx = -reach;
+            while dx <= reach {
+                if dx != 0 || dy != 0 {
+                    bits |= Self::bit(dx, dy);
+                }
+                dx += 1;
+            }
+            dy += 1;
+        }
+        Self(bits)
+    }
+
+    /// The neighbourhood of `offsets`, unless one of them is the square
+    /// itself or is out of reach.
+    pub fn from_offsets(offsets: impl IntoIterator<Item=(i32, i32)>) -> Option<Self> {
+        let mut bits = 0;
+        for (dx, dy) in offsets {
+            if (dx, dy) == (0, 0) || dx.abs() > Self::REACH || dy.abs() > Self::REACH {
+                return None;
+            }
+            bits |= Self::bit(dx, dy);
+        }
+        Some(Self(bits))
+    }
+
+    /// How many squares are in the neighbourhood.
+    pub fn count(&self) -> usize {
+        self.0.count_ones() as usize
+    }
+
+    /// The offsets, from the top left row by row.
+    pub fn offsets(&self) -> impl Iterator<Item=(i32, i32)> + use<> {
+        let mut bits = self.0;
+        std::iter::from_fn(move || {
+            if bits == 0 {
+                return None;
+            }
+            let i = bits.trailing_zeros() as i32;
+            bits &= bits - 1;
+            Some((i % Self::SIDE - Self::REACH, i / Self::SIDE - Self::REACH))
+        })
+    }
+
+    /// The name of the neighbourhood, if it has one.
+    fn name(&self) -> Option<&'static str> {
+        match *self {
+            Self::KING => Some("king"),
+            Self::CROSS => Some("cross"),
+            Self::KNIGHT => Some("knight"),
+            Self::RADIUS_2 => Some("radius2"),
+            _ => None,
+        }
+    }
+}
+impl Default for Neighborhood {
+    fn default() -> Self {
+        Self::KING
+    }
+}
+
+/// Writes the name of a neighbourhood that has one, such as `knight`, and
+/// otherwise the offsets as `x,y` separated by `/`, such as `0,-2/0,2`.
+impl fmt::Display for Neighborhood {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if let Some(name) = self.name() {
+            return write!(f, "{}", name);
         }
+        let offsets: Vec<_> = self.offsets()
+            .map(|(dx, dy)| format!("{},{}", dx, dy))
+            .collect();
+        write!(f, "{}", offsets.join("/"))
     }
 }
+impl std::str::FromStr for Neighborhood {
+    type Err = ParseNeighborhoodError;
 
-trait MineBoard {
-    type IndexShape;
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let bad = || ParseNeighborhoodError(s.to_string());
+        match s {
+            "king" => return Ok(Self::KING),
+            "cross" => return Ok(Self::CROSS),
+            "knight" => return Ok(Self::KNIGHT),
+            "radius2" => return Ok(Self::RADIUS_2),
+            _ => {},
+        }
+        let offsets = s.split('/')
+            .map(|offset| {
+                let (dx, dy) = offset.split_once(',').ok_or_else(bad)?;
+                Ok((dx.parse().map_err(|_| bad())?, dy.parse().map_err(|_| bad())?))
+            })
+            .collect::<Result<Vec<_>, _>>()?;
+        Self::from_offsets(offsets).ok_or_else(bad)
+    }
+}
 
-    const NEIGHBORHOOD: [Self::IndexShape];
-    const X: i32;
-    const Y: i32;
-    const SQ_SIZE: usize; 
+/// A neighbourhood that isn't one of the names or a list of offsets in reach
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ParseNeighborhoodError(pub String);
+impl fmt::Display for ParseNeighborhoodError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f, "{:?} is not a neighborhood, expected king, cross, knight, radius2 \
+            or offsets like 0,-2/0,2 up to {} away", self.0, Neighborhood::REACH
+        )
+    }
+}
+impl std::error::Error for ParseNeighborhoodError {}
 
-    fn open(&mut self) -> Cell;
+/// Everything that decides which squares of a board are next to each other
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash)]
+pub struct Topology {
+    pub grid: Grid,
+    /// Which squares are next to each other on a square grid
+    pub neighborhood: Neighborhood,
+    pub wrap: Wrap,
 }
+impl Topology {
+    /// Whether this is the plain square grid with edges that don't wrap.
+    pub fn is_default(&self) -> bool {
+        *self == Self::default()
+    }
+
+    /// How many neighbours a square has, away from edges that don't wrap.
+    pub fn neighbors(&self) -> usize {
+        match self.grid {
+            Grid::Square => self.neighborhood.count(),
+            Grid::Hex => 6,
+        }
+    }
+
+    /// The most mines a square can hold with this many neighbours, so that
+    /// no number goes past [`Cell::MAX_NUM`].
+    pub fn max_mines_per_square(&self) -> u8 {
+        let most = Cell::MAX_NUM as usize / self.neighbors().max(1);
+        most.min(Cell::MAX_MINES as usize) as u8
+    }
+}
+
+/// Writes the parts that aren't the default as `name value` pairs, such as
+/// `neighborhood knight wrap both`, or nothing at all for the default.
+impl fmt::Display for Topology {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let mut parts = vec![];
+        if self.grid != Grid::default() {
+            parts.push(format!("grid {}", self.grid));
+        }
+        if self.neighborhood != Neighborhood::default() {
+            parts.push(format!("neighborhood {}", self.neighborhood));
+        }
+        if self.wrap.any() {
+            parts.push(format!("wrap {}", self.wrap));
+        }
+        write!(f, "{}", parts.join(" "))
+    }
+}
+impl std::str::FromStr for Topology {
+    type Err = ParseTopologyError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let bad = || ParseTopologyError(s.to_string());
+        let mut topology = Self::default();
+        let mut words = s.split_whitespace();
+        while let Some(name) = words.next() {
+            let value = words.next().ok_or_else(bad)?;
+            match name {
+                "grid" => topology.grid = value.parse().map_err(|_| bad())?,
+                "neighborhood" => topology.neighborhood = value.parse().map_err(|_| bad())?,
+                "wrap" => topology.wrap = value.parse().map_err(|_| bad())?,
+                _ => return Err(bad()),
+            }
+        }
+        Ok(topology)
+    }
+}
+
+/// Text that isn't `grid`, `neighborhood` and `wrap` each followed by a value
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ParseTopologyError(pub String);
+impl fmt::Display for ParseTopologyError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{:?} is not a topology, expected grid, neighborhood and wrap settings", self.0)
+    }
+}
+impl std::error::Error for ParseTopologyError {}
+
+/// How many layouts [`Board::generate_no_guess`] tries before giving up.
+pub const NO_GUESS_ATTEMPTS: usize = 1000;
+
+/// Why a board could not be generated
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum GenerateError {
+    /// There are more mines than squares to put them in
+    TooManyMines { num_mines: usize, free: usize },
+    /// No layout that can be solved without guessing was found
+    NoGuessFailed { attempts: usize },
+    /// Squares can hold several mines, which the solver doesn't know about
+    SeveralMinesPerSquare,
+}
+impl fmt::Display for GenerateError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            GenerateError::TooManyMines { num_mines, free } => write!(
+                f, "cannot place {} mines in {} free squares", num_mines, free
+            ),
+            GenerateError::NoGuessFailed { attempts } => write!(
+                f, "no board solvable without guessing found in {} attempts", attempts
+            ),
+            GenerateError::SeveralMinesPerSquare => write!(
+                f, "boards with several mines to a square can't be solved without guessing"
+            ),
+        }
+    }
+}
+impl std::error::Error for GenerateError {}
 
 /// The Minesweeper board
+#[derive(Debug, Clone)]
 pub struct Board {
     cells: Vec<Cell>,
     width: usize,
     height: usize,
+    topology: Topology,
+    mines_per_square: u8,
+    seed: Option<u64>,
 }
 impl Board {
     pub const X: i32 = 0;
@@ -57,12 +589,6 @@ impl Board {
     pub const SQ_U32: u32 = SQ_SIZE as u32; 
     pub const SQ_I32: i32 = SQ_SIZE as i32; 
 
-    const NEIGHBORHOOD: [(i32, i32); 8] = [
-        (-1, -1), (0, -1), (1, -1),
-        (-1,  0),          (1,  0),
-        (-1,  1), (0,  1), (1,  1),
-    ];
-
     /// Converts coordinates in pixels to the corresponding square
     pub fn coord(x: i32, y: i32) -> (usize, usize) {
         let (rel_x, rel_y) = (
@@ -72,6 +598,68 @@ impl Board {
         (rel_x/Self::SQ_SIZE, rel_y/Self::SQ_SIZE)
     }
 
+    /// The square under the pixel at `(x, y)`, if there is one. Unlike
+    /// [`Board::coord`] this knows about hexagons.
+    pub fn square_at(&self, x: i32, y: i32) -> Option<(usize, usize)> {
+        let (rel_x, rel_y) = (x - Self::X, y - Self::Y);
+        let (width, height) = self.pixel_size();
+        if rel_x < 0 || rel_y < 0 || rel_x >= width as i32 || rel_y >= height as i32 {
+            return None;
+        }
+        match self.grid() {
+            Grid::Square => Some(Self::coord(x, y)),
+            Grid::Hex => self.hex_at(rel_x, rel_y),
+        }
+    }
+
+    /// The hexagon under a pixel measured from the board's top left.
+    ///
+    /// Hexagons are as wide and as tall as squares, with their points at
+    /// the top and bottom, so the rows overlap by a quarter. A pixel belongs
+    /// to the nearest centre, counting the square of the height a third
+    /// over, which splits the overlap along the slopes. The pixels in the
+    /// notches along the edges of the board belong to no hexagon.
+    fn hex_at(&self, x: i32, y: i32) -> Option<(usize, usize)> {
+        // in half pixels, from the middle of the pixel, so that half a
+        // hexagon is as many half pixels across as a square is pixels
+        let (x, y) = (2*x + 1, 2*y + 1);
+        let (half, row_height) = (SQ_I32, 2*self.grid().row_height() as i32);
+        let shift = |r: i32| if r % 2 == 1 { half } else { 0 };
+
+        // the boxes of the two rows that can overlap the pixel
+        let row = y/row_height;
+        let (dx, dy, p) = [row - 1, row].into_iter()
+            .map(|r| ((x - shift(r)).div_euclid(2*half), r))
+            .filter(|&(c, r)| c >= 0 && r >= 0)
+            .filter(|&(c, r)| (c as usize) < self.width && (r as usize) < self.height)
+            .map(|(c, r)| (
+                (x - (2*c*half + half + shift(r))).abs(),
+                (y - (r*row_height + half)).abs(),
+                (c as usize, r as usize),
+            ))
+            .min_by_key(|&(dx, dy, _)| 3*dx*dx + 4*dy*dy)?;
+        // between its sides, and under its slopes
+        (dx <= half && 2*dy <= 2*half - dx).then_some(p)
+    }
+
+    /// The top left pixel of the box the square at `(x, y)` is drawn in,
+    /// which may be past the edge of the board.
+    pub fn square_origin(&self, (x, y): (usize, usize)) -> (i32, i32) {
+        let shift = match self.grid() {
+            Grid::Hex if y % 2 == 1 => SQ_SIZE/2,
+            _ => 0,
+        };
+        (
+            (x*SQ_SIZE + shift) as i32 + Self::X,
+            (y*self.grid().row_height()) as i32 + Self::Y,
+        )
+    }
+
+    /// The width and height of the board in pixels.
+    pub fn pixel_size(&self) -> (usize, usize) {
+        self.grid().span(self.width, self.height)
+    }
+
     /// Creates a new board.
     pub fn new(width: usize, height: usize) -> Self {
         let cells = vec![Cell::default(); width*height];
@@ -80,99 +668,251 @@ impl Board {
             cells,
             width,
             height,
+            topology: Topology::default(),
+            mines_per_square: 1,
+            seed: None,
         }
     }
 
-    /// Clears out the board to the default Cell.
-    pub fn reset(&mut self) {
-        for x in 0..self.width {
-            for y in 0..self.height {
-                self[(x, y)] = Cell::default();
-            }
+    /// Creates a board with mines on exactly the squares in `mines`. A
+    /// square that is in `mines` more than once gets that many mines.
+    pub fn with_mines(width: usize, height: usize, mines: &[(usize, usize)]) -> Self {
+        let mut board = Self::new(width, height);
+        for &p in mines {
+            board[p].mines += 1;
+            board.mines_per_square = board.mines_per_square.max(board[p].mines);
         }
+        board.compute_numbers();
+        board
     }
 
-    /// Opens a square. Returns whether or not it loses the game.
-    pub fn open(&mut self, p: (usize, usize)) -> bool {
-        if !self[p].open && !self[p].flag {
-            self[p].open = true; 
-            if self[p].num == 0 && !self[p].mine {
-                for neighbor in self.get_neighborhood(p) {
-                    if !(self[neighbor].open || self[neighbor].mine) {
-                        self.open(neighbor);
-                    }
-                }
+    pub fn width(&self) -> usize { self.width }
+
+    pub fn topology(&self) -> Topology { self.topology }
+
+    /// Sets which squares are next to each other, numbering any mines
+    /// already placed again.
+    ///
+    /// A hex board always has the six hexagons around as neighbours, and
+    /// only wraps from top to bottom when it has an even number of rows,
+    /// since otherwise the rows wouldn't line up across the edge.
+    pub fn set_topology(&mut self, topology: Topology) {
+        self.topology = topology;
+        if topology.grid == Grid::Hex {
+            self.topology.neighborhood = Neighborhood::default();
+            if self.height % 2 == 1 {
+                self.topology.wrap.vertical = false;
             }
-            self[p].mine
-        } else {
-            false
         }
+        self.compute_numbers();
     }
 
-    /// Chords. Returns whether or not it loses the game
-    pub fn chord(&mut self, p: (usize, usize)) -> bool {
-        if self[p].mine == false && self[p].open {
-            let flags = self.get_neighborhood(p)
-                .filter(|(x, y)| self[(*x, *y)].flag)
-                .count();
-            println!("flags: {}, num: {}", flags, self[p].num);
-            if self[p].num as usize == flags {
-                for neighbor in self.get_neighborhood(p) {
-                    if self.open(neighbor) {
-                        return true
-                    }
-                }
-            } 
-        } 
-        false
-    }
+    pub fn grid(&self) -> Grid { self.topology.grid }
 
-    pub fn width(&self) -> usize { self.width }
+    pub fn neighborhood(&self) -> Neighborhood { self.topology.neighborhood }
+
+    pub fn wrap(&self) -> Wrap { self.topology.wrap }
+
+    /// Sets which edges wrap around, numbering any mines already placed
+    /// again.
+    pub fn set_wrap(&mut self, wrap: Wrap) {
+        self.set_topology(Topology { wrap, ..self.topology });
+    }
 
     pub fn height(&self) -> usize { self.height }
 
-    fn get_neighborhood(
+    /// The seed this board was generated from, if it was generated from one.
+    pub fn seed(&self) -> Option<u64> { self.seed }
+
+    /// Sets the seed for a board read back from text, which doesn't keep it.
+    pub fn set_seed(&mut self, seed: Option<u64>) { self.seed = seed; }
+
+    /// Sets the most mines one square can hold, from 1 up to
+    /// [`Topology::max_mines_per_square`], for the mines placed from now on.
+    pub fn set_mines_per_square(&mut self, mines: u8) {
+        self.mines_per_square = mines.clamp(1, self.topology.max_mines_per_square());
+    }
+
+    /// The squares around `(x, y)` that are on the board, reaching across
+    /// the edges that wrap. On a board too small for the wrap to give every
+    /// neighbour a different square, each one is only given once, and never
+    /// `(x, y)` itself.
+    pub fn get_neighborhood(
         &self, 
         (x, y): (usize, usize)) 
     -> impl Iterator<Item=(usize, usize)> {
         let (width, height) = (self.width() as i32, self.height() as i32);
-        let (x, y) = (x as i32, y as i32);
-        Self::NEIGHBORHOOD
-            .iter()
-            .map(move |(dx, dy)| (x+dx, y+dy))
-            .filter(move |(x, y)| *x >= 0 && *x < width && *y >= 0 && *y < height)
-            .map(|(x, y)| (x as usize, y as usize))
+        let Wrap { horizontal, vertical } = self.wrap();
+        let wrap = |v: i32, len: i32, wraps: bool| match v {
+            _ if wraps => Some(v.rem_euclid(len)),
+            _ if v >= 0 && v < len => Some(v),
+            _ => None,
+        };
+        let square = move |(dx, dy): (i32, i32)| {
+            wrap(x as i32 + dx, width, horizontal)
+                .zip(wrap(y as i32 + dy, height, vertical))
+                .map(|(x, y)| (x as usize, y as usize))
+        };
+        let neighborhood = match self.grid() {
+            Grid::Square => self.neighborhood(),
+            Grid::Hex if y % 2 == 1 => Neighborhood::HEX_SHIFTED,
+            Grid::Hex => Neighborhood::HEX,
+        };
+        // only a wrap across fewer squares than the neighbourhood spans can
+        // reach the same square twice
+        let reach = 2*Neighborhood::REACH;
+        let may_repeat = (horizontal && width <= reach) || (vertical && height <= reach);
+
+        neighborhood.offsets().enumerate().filter_map(move |(i, offset)| {
+            let q = square(offset)?;
+            let repeat = may_repeat && (
+                q == (x, y) || neighborhood.offsets().take(i).any(|o| square(o) == Some(q))
+            );
+            (!repeat).then_some(q)
+        })
     }
 
-    pub fn generate(&mut self, num_mines: usize, (x, y): (usize, usize)) {
-        // generate mines
-        let mut mines = vec![false; self.width() * self.height() - 1];
-        for i in 0..num_mines {
-            mines[i] = true;
-        }
-        mines.shuffle(&mut thread_rng());
+    /// Places mines randomly, keeping the square at `p` free.
+    ///
+    /// A fresh seed is drawn for every call and recorded on the board, so
+    /// the layout can always be reproduced with [`Board::generate_seeded`].
+    pub fn generate(&mut self, num_mines: usize, p: (usize, usize)) {
+        let seed = thread_rng().gen();
+        self.generate_seeded(num_mines, p, seed);
+    }
 
-        let skip_idx = y * self.width() + x;
-        // assign mines
-        for (i, m) in mines.iter().enumerate() {
-            if i >= skip_idx {
-                self.cells[i+1].mine = *m;
-            } else {
-                self.cells[i].mine = *m;
-            }
+    /// Places mines from `seed`, keeping the square at `p` free.
+    ///
+    /// The same seed, dimensions, mine count and first click always give
+    /// the same layout, on every platform.
+    pub fn generate_seeded(&mut self, num_mines: usize, p: (usize, usize), seed: u64) {
+        self.generate_with_rng(num_mines, p, &mut ChaCha8Rng::seed_from_u64(seed));
+        self.seed = Some(seed);
+    }
+
+    /// Places mines using `rng`, keeping the square at `p` free.
+    pub fn generate_with_rng<R: Rng + ?Sized>(
+        &mut self, 
+        num_mines: usize, 
+        p: (usize, usize),
+        rng: &mut R,
+    ) {
+        self.place_mines(num_mines, &[p], rng);
+        self.seed = None;
+    }
+
+    /// Places mines so that the whole board can be cleared from `p` without
+    /// guessing, drawing a fresh seed and recording it on the board.
+    pub fn generate_no_guess(
+        &mut self, 
+        num_mines: usize, 
+        p: (usize, usize),
+    ) -> Result<(), GenerateError> {
+        let seed = thread_rng().gen();
+        self.generate_no_guess_seeded(num_mines, p, seed)
+    }
+
+    /// Places mines from `seed` so that the whole board can be cleared from
+    /// `p` without guessing.
+    pub fn generate_no_guess_seeded(
+        &mut self, 
+        num_mines: usize, 
+        p: (usize, usize),
+        seed: u64,
+    ) -> Result<(), GenerateError> {
+        let mut rng = ChaCha8Rng::seed_from_u64(seed);
+        self.generate_no_guess_with_rng(num_mines, p, &mut rng, NO_GUESS_ATTEMPTS)?;
+        self.seed = Some(seed);
+        Ok(())
+    }
+
+    /// Places mines using `rng` so that the whole board can be cleared from
+    /// `p` by [`solver::solve`](crate::solver::solve), trying at most
+    /// `max_attempts` layouts.
+    ///
+    /// The squares around `p` are kept free as well whenever there is room,
+    /// so the first click always opens an area to start from.
+    pub fn generate_no_guess_with_rng<R: Rng + ?Sized>(
+        &mut self, 
+        num_mines: usize, 
+        p: (usize, usize),
+        rng: &mut R,
+        max_attempts: usize,
+    ) -> Result<(), GenerateError> {
+        if self.mines_per_square > 1 {
+            return Err(GenerateError::SeveralMinesPerSquare);
+        }
+        let cells = self.width * self.height;
+        let mut excluded: Vec<_> = self.get_neighborhood(p).collect();
+        excluded.push(p);
+        if num_mines + excluded.len() > cells {
+            excluded = vec![p];
+        }
+        if num_mines + excluded.len() > cells {
+            return Err(GenerateError::TooManyMines {
+                num_mines,
+                free: cells - excluded.len(),
+            });
         }
 
-        // generate numbers
-        for x in 0..self.width {
-            for y in 0..self.height {
-                self[(x, y)].num = self.get_neighborhood((x, y))
-                    .map(|p| self[p])
-                    .map(|sq| sq.mine as u8)
-                    .sum();
+        for _ in 0..max_attempts {
+            self.reset();
+            self.place_mines(num_mines, &excluded, rng);
+
+            let mut attempt = self.clone();
+            attempt.open(p);
+            if crate::solver::solve(&mut attempt) {
+                return Ok(());
             }
         }
+        self.reset();
+        Err(GenerateError::NoGuessFailed { attempts: max_attempts })
+    }
+}
+
+/// A grid of squares or hexagons, with the neighbours its topology gives
+impl MineBoard for Board {
+    type Square = (usize, usize);
+
+    /// Row by row from the top left.
+    fn squares(&self) -> impl Iterator<Item=Self::Square> + use<> {
+        let width = self.width;
+        (0..self.height).flat_map(move |y| (0..width).map(move |x| (x, y)))
+    }
+
+    fn neighbors(&self, p: Self::Square) -> impl Iterator<Item=Self::Square> + use<> {
+        self.get_neighborhood(p)
+    }
+
+    fn cell(&self, p: Self::Square) -> &Cell { &self[p] }
+
+    fn cell_mut(&mut self, p: Self::Square) -> &mut Cell { &mut self[p] }
+
+    fn mines_per_square(&self) -> u8 { self.mines_per_square }
+
+    /// Clears out the board to the default Cell, forgetting the seed.
+    fn reset(&mut self) {
+        self.cells.fill(Cell::default());
+        self.seed = None;
+    }
+
+    fn num_mines(&self) -> usize {
+        self.cells.iter().map(|c| c.mines as usize).sum()
+    }
+
+    fn num_flags(&self) -> usize {
+        self.cells.iter().map(|c| c.flags as usize).sum()
+    }
+
+    fn num_opened(&self) -> usize {
+        self.cells.iter().filter(|c| c.open && !c.is_mine()).count()
+    }
+
+    fn is_won(&self) -> bool {
+        self.cells.iter().all(|c| c.open || c.is_mine())
     }
 }
+
 impl Index<(usize, usize)> for Board {
     type Output = Cell;
     fn index(&self, (x, y): (usize, usize)) -> &Self::Output {
@@ -185,6 +925,195 @@ impl IndexMut<(usize, usize)> for Board {
     }
 }
 
+/// Why a board couldn't be read from text
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ParseBoardError {
+    /// The line the problem is on, counting from 1
+    pub line: usize,
+    /// The column the problem is in, counting from 1
+    pub column: usize,
+    pub kind: ParseBoardErrorKind,
+}
+
+/// What was wrong with a board's text
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ParseBoardErrorKind {
+    /// There are no rows
+    Empty,
+    /// This character doesn't stand for any square
+    UnknownSquare(char),
+    /// The row is a different width to the first one
+    Width { expected: usize, found: usize },
+    /// An open square's number doesn't match the mines around it
+    Number { expected: u8, found: u8 },
+    /// An open square shows `#` but has fewer than ten mines around it
+    FewerThanTen { expected: u8 },
+    /// A line after the grid isn't a square on it with the mines and flags
+    /// it shows, and no more than a square can hold
+    Count,
+}
+impl fmt::Display for ParseBoardError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "line {}, column {}: ", self.line, self.column)?;
+        match self.kind {
+            ParseBoardErrorKind::Empty => write!(f, "the board has no rows"),
+            ParseBoardErrorKind::UnknownSquare(c) => write!(
+                f, "{:?} is not a square, expected one of .*Ff0-9#X", c
+            ),
+            ParseBoardErrorKind::Width { expected, found } => write!(
+                f, "the row is {} squares wide but the first row is {}", found, expected
+            ),
+            ParseBoardErrorKind::Number { expected, found } => write!(
+                f, "the square shows {} but has {} mines around it", found, expected
+            ),
+            ParseBoardErrorKind::FewerThanTen { expected } => write!(
+                f, "the square shows # for ten or more but has {} mines around it", expected
+            ),
+            ParseBoardErrorKind::Count => write!(
+                f, "expected a square on the board like 3,1 mines 2 flags 1"
+            ),
+        }
+    }
+}
+impl std::error::Error for ParseBoardError {}
+
+/// Writes the board as a grid, one line per row: `.` is a closed square,
+/// `*` a closed mine, `F` a flagged mine and `f` a flag on a safe square.
+/// Open squares show their number, `#` for ten or more, or `X` for an
+/// opened mine. A square with several mines or flags is written as if it
+/// had one, and after a blank line below the grid as `3,1 mines 2 flags 1`.
+impl fmt::Display for Board {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for y in 0..self.height {
+            for x in 0..self.width {
+                let cell = self[(x, y)];
+                let c = match (cell.open, cell.is_flagged(), cell.is_mine()) {
+                    (true, _, true) => 'X',
+                    (true, _, false) if cell.num >= 10 => '#',
+                    (true, _, false) => (b'0' + cell.num) as char,
+                    (false, true, true) => 'F',
+                    (false, true, false) => 'f',
+                    (false, false, true) => '*',
+                    (false, false, false) => '.',
+                };
+                write!(f, "{}", c)?;
+            }
+            writeln!(f)?;
+        }
+        let mut stacked = self.squares()
+            .filter(|&p| self[p].mines > 1 || self[p].flags > 1)
+            .peekable();
+        if stacked.peek().is_some() {
+            writeln!(f)?;
+        }
+        for (x, y) in stacked {
+            let cell = self[(x, y)];
+            writeln!(f, "{},{} mines {} flags {}", x, y, cell.mines, cell.flags)?;
+        }
+        Ok(())
+    }
+}
+
+/// Reads a board written by its [`Display`](fmt::Display). Blank lines at
+/// the start and end are skipped, and the numbers on open squares are
+/// checked against the mines.
+impl std::str::FromStr for Board {
+    type Err = ParseBoardError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Self::from_text(s, Topology::default())
+    }
+}
+
+impl Board {
+    /// Reads a board with the given topology, written by its
+    /// [`Display`](fmt::Display), which doesn't say what its topology is.
+    pub fn from_text(s: &str, topology: Topology) -> Result<Self, ParseBoardError> {
+        let error = |line, column, kind| ParseBoardError { line, column, kind };
+        let rows: Vec<(usize, &str)> = s.lines()
+            .enumerate()
+            .map(|(i, line)| (i + 1, line.trim_end()))
+            .skip_while(|(_, line)| line.is_empty())
+            .collect();
+        let end = rows.iter().rposition(|(_, line)| !line.is_empty())
+            .ok_or(error(1, 1, ParseBoardErrorKind::Empty))?;
+        let rows = &rows[..=end];
+        // a blank line parts the grid from the squares with several mines
+        let (rows, counts) = match rows.iter().position(|(_, line)| line.is_empty()) {
+            Some(blank) => (&rows[..blank], &rows[blank + 1..]),
+            None => (rows, &[][..]),
+        };
+
+        let width = rows[0].1.chars().count();
+        let height = rows.len();
+        let mut board = Board::new(width, height);
+        board.set_topology(topology);
+        // the numbers written on open squares, checked once every mine is in
+        let mut numbers = vec![];
+        for (y, &(line, row)) in rows.iter().enumerate() {
+            let found = row.chars().count();
+            if found != width {
+                let column = width.min(found) + 1;
+                return Err(error(line, column, ParseBoardErrorKind::Width { expected: width, found }));
+            }
+            for (x, c) in row.chars().enumerate() {
+                let cell = &mut board[(x, y)];
+                match c {
+                    '.' => {},
+                    '*' => cell.mines = 1,
+                    'F' => { cell.mines = 1; cell.flags = 1 },
+                    'f' => cell.flags = 1,
+                    'X' => { cell.mines = 1; cell.open = true },
+                    '0'..='9' => {
+                        cell.open = true;
+                        numbers.push(((x, y), (line, x + 1), Some(c as u8 - b'0')));
+                    },
+                    '#' => {
+                        cell.open = true;
+                        numbers.push(((x, y), (line, x + 1), None));
+                    },
+                    _ => return Err(error(line, x + 1, ParseBoardErrorKind::UnknownSquare(c))),
+                }
+            }
+        }
+
+        for &(line, text) in counts.iter().filter(|(_, text)| !text.is_empty()) {
+            let bad = || error(line, 1, ParseBoardErrorKind::Count);
+            let (p, mines, flags) = match text.split_whitespace().collect::<Vec<_>>()[..] {
+                [square, "mines", mines, "flags", flags] => {
+                    let (x, y) = square.split_once(',').ok_or_else(bad)?;
+                    let square = |s: &str| s.parse::<usize>().map_err(|_| bad());
+                    let count = |s: &str| s.parse::<u8>().map_err(|_| bad());
+                    ((square(x)?, square(y)?), count(mines)?, count(flags)?)
+                },
+                _ => return Err(bad()),
+            };
+            let most = board.topology.max_mines_per_square();
+            if p.0 >= width || p.1 >= height || mines.max(flags) > most {
+                return Err(bad());
+            }
+            let cell = &mut board[p];
+            if cell.is_mine() != (mines > 0) || cell.is_flagged() != (flags > 0) {
+                return Err(bad());
+            }
+            (cell.mines, cell.flags) = (mines, flags);
+            board.mines_per_square = board.mines_per_square.max(mines).max(flags);
+        }
+
+        board.compute_numbers();
+        for (p, (line, column), found) in numbers {
+            let expected = board[p].num;
+            let kind = match found {
+                Some(found) if found != expected => ParseBoardErrorKind::Number { expected, found },
+                None if expected < 10 => ParseBoardErrorKind::FewerThanTen { expected },
+                _ => continue,
+            };
+            return Err(error(line, column, kind));
+        }
+        Ok(board)
+    }
+}
+
 
 #[cfg(test)]
 pub mod test {
@@ -319,7 +1248,7 @@ pub mod test {
         println!("== BEFORE ==");
         for x in 0..width {
             for y in 0..height {
-                print!("{}", match board[(x, y)].mine {
+                print!("{}", match board[(x, y)].is_mine() {
                     true => 'X',
                     false => '.',
                 });
@@ -332,7 +1261,7 @@ pub mod test {
         println!("== AFTER ==");
         for x in 0..width {
             for y in 0..height {
-                print!("{}", match board[(x, y)].mine {
+                print!("{}", match board[(x, y)].is_mine() {
                     true => 'X',
                     false => '.',
                 });
@@ -340,4 +1269,496 @@ pub mod test {
             println!();
         }
     }
+
+    #[test]
+    pub fn win_by_open() {
+        // 3x1 with a mine on the right: opening (0, 0) only opens
+        // itself since it has no zero-squares to flood from
+        let mut board = Board::with_mines(3, 1, &[(2, 0)]);
+        assert!(!board.open((1, 0)));
+        assert!(!board.is_won());
+        assert!(!board.open((0, 0)));
+        assert!(board.is_won());
+        assert_eq!(board.num_opened(), 2);
+    }
+
+    #[test]
+    pub fn win_by_chord() {
+        let mut board = Board::with_mines(3, 3, &[(0, 0)]);
+        board.open((1, 1));
+        board.open((1, 0));
+        board.open((0, 1));
+        assert!(!board.is_won());
+
+        board[(0, 0)].flags = 1;
+        assert!(!board.chord((1, 1)));
+        assert!(board.is_won());
+    }
+
+    #[test]
+    pub fn win_after_flood_fill() {
+        let mut board = Board::with_mines(5, 5, &[(4, 4)]);
+        assert!(!board.open((0, 0)));
+        assert!(board.is_won());
+        assert_eq!(board.num_opened(), 24);
+        assert!(!board[(4, 4)].open);
+
+        board.flag_mines();
+        assert!(board[(4, 4)].is_flagged());
+    }
+
+    #[test]
+    pub fn reopening_does_not_count() {
+        let mut board = Board::with_mines(3, 1, &[(2, 0)]);
+        board.open((1, 0));
+        board.open((1, 0));
+        board.chord((1, 0));
+        assert_eq!(board.num_opened(), 1);
+        assert!(!board.is_won());
+    }
+
+    #[test]
+    pub fn open_mine_does_not_win() {
+        let mut board = Board::with_mines(2, 1, &[(1, 0)]);
+        board.open((0, 0));
+        assert!(board.is_won());
+
+        let mut board = Board::with_mines(2, 1, &[(1, 0)]);
+        assert!(board.open((1, 0)));
+        assert!(!board.is_won());
+    }
+
+    #[test]
+    pub fn several_mines() {
+        // two mines in the middle, one in the corner
+        let board = Board::with_mines(3, 3, &[(1, 1), (1, 1), (0, 0)]);
+        assert_eq!(board.mines_per_square(), 2);
+        assert_eq!((board.num_mines(), board[(1, 0)].num, board[(2, 2)].num), (3, 3, 2));
+    }
+
+    #[test]
+    pub fn cycle_flags() {
+        let mut board = Board::with_mines(3, 3, &[(1, 1), (1, 1), (0, 0)]);
+        // flags count up to the most a square holds, then come off
+        assert_eq!(board.cycle_flags((1, 1)), 1);
+        assert_eq!(board.num_flags(), 1);
+        assert_eq!(board.cycle_flags((1, 1)), 2);
+        assert_eq!(board.num_flags(), 2);
+        assert_eq!(board.cycle_flags((1, 1)), 0);
+        assert_eq!(board.num_flags(), 0);
+    }
+
+    #[test]
+    pub fn chord_counts_flags() {
+        let mut board = Board::with_mines(3, 3, &[(1, 1), (1, 1), (0, 0)]);
+        board.open((1, 0));
+        board.cycle_flags((0, 0));
+        board.cycle_flags((1, 1));
+        // one flag isn't enough for the two mines
+        assert!(!board.chord((1, 0)));
+        assert!(!board[(2, 0)].open);
+        board.cycle_flags((1, 1));
+        assert!(!board.chord((1, 0)));
+        assert!(board[(2, 0)].open);
+    }
+
+    #[test]
+    pub fn win_with_several_mines() {
+        let mut board = Board::with_mines(3, 3, &[(1, 1), (1, 1), (0, 0)]);
+        for p in [(1, 0), (2, 0), (0, 1), (0, 2), (1, 2), (2, 1), (2, 2)] {
+            assert!(!board.open(p));
+        }
+        assert!(board.is_won());
+        board.flag_mines();
+        assert_eq!(board.num_flags(), 3);
+    }
+
+    #[test]
+    pub fn generate_several_mines() {
+        // generated boards put no more than the most on a square
+        let mut board = Board::new(4, 4);
+        board.set_mines_per_square(3);
+        board.generate_seeded(30, (0, 0), 5);
+        assert_eq!(board.num_mines(), 30);
+        assert!(board.squares().all(|p| board[p].mines <= 3));
+        assert!(!board[(0, 0)].is_mine());
+        assert!(board.squares().any(|p| board[p].mines > 1));
+        for p in board.squares() {
+            let around: u8 = board.get_neighborhood(p).map(|q| board[q].mines).sum();
+            assert_eq!(board[p].num, around);
+        }
+    }
+
+    #[test]
+    pub fn set_mines_per_square() {
+        let mut board = Board::new(4, 4);
+        board.set_mines_per_square(0);
+        assert_eq!(board.mines_per_square(), 1);
+        board.set_mines_per_square(9);
+        assert_eq!(board.mines_per_square(), Cell::MAX_MINES);
+    }
+
+    #[test]
+    pub fn generate_seeded() {
+        let mines = |board: &Board| -> Vec<(usize, usize)> {
+            (0..board.height())
+                .flat_map(|y| (0..board.width()).map(move |x| (x, y)))
+                .filter(|&p| board[p].is_mine())
+                .collect()
+        };
+
+        let mut a = Board::new(16, 16);
+        let mut b = Board::new(16, 16);
+        a.generate_seeded(40, (3, 4), 0xdead_beef);
+        b.generate_seeded(40, (3, 4), 0xdead_beef);
+        assert_eq!(a.seed(), Some(0xdead_beef));
+        assert_eq!(mines(&a).len(), 40);
+        assert_eq!(mines(&a), mines(&b));
+        assert!(!a[(3, 4)].is_mine());
+
+        b.reset();
+        assert_eq!(b.seed(), None);
+        b.generate_seeded(40, (3, 4), 0xdead_bef0);
+        assert_ne!(mines(&a), mines(&b));
+
+        // pinned so that a change in the rng or the shuffle is caught
+        let mut c = Board::new(5, 5);
+        c.generate_seeded(5, (0, 0), 1);
+        assert_eq!(mines(&c), vec![(4, 0), (2, 1), (3, 2), (4, 2), (1, 4)]);
+    }
+
+    #[test]
+    pub fn generate_no_guess() {
+        let mut board = Board::new(16, 16);
+        board.generate_no_guess_seeded(40, (7, 7), 7).unwrap();
+        assert_eq!(board.seed(), Some(7));
+        assert_eq!(board.num_mines(), 40);
+        assert_eq!(board[(7, 7)].num, 0);
+
+        board.open((7, 7));
+        assert!(crate::solver::solve(&mut board));
+
+        let mut again = Board::new(16, 16);
+        again.generate_no_guess_seeded(40, (7, 7), 7).unwrap();
+        for y in 0..16 {
+            for x in 0..16 {
+                assert_eq!(again[(x, y)].is_mine(), board[(x, y)].is_mine());
+            }
+        }
+    }
+
+    #[test]
+    pub fn generate_no_guess_fails() {
+        let mut board = Board::new(4, 4);
+        assert_eq!(
+            board.generate_no_guess_seeded(16, (0, 0), 0),
+            Err(GenerateError::TooManyMines { num_mines: 16, free: 15 }),
+        );
+
+        let mut rng = ChaCha8Rng::seed_from_u64(0);
+        assert_eq!(
+            board.generate_no_guess_with_rng(14, (0, 0), &mut rng, 3),
+            Err(GenerateError::NoGuessFailed { attempts: 3 }),
+        );
+    }
+
+    #[test]
+    pub fn text_round_trip() {
+        let text = "\
+            .*F1\n\
+            f321\n\
+            X100\n";
+        let board: Board = text.parse().unwrap();
+        assert_eq!((board.width(), board.height()), (4, 3));
+        assert_eq!(board.num_mines(), 3);
+        assert_eq!(board.num_flags(), 2);
+        assert_eq!(board.num_opened(), 7);
+        assert!(board[(1, 0)].is_mine() && !board[(1, 0)].is_flagged());
+        assert!(board[(0, 1)].is_flagged() && !board[(0, 1)].is_mine());
+        assert!(board[(0, 2)].open && board[(0, 2)].is_mine());
+        assert_eq!(board.to_string(), text);
+
+        let mut board = Board::new(5, 4);
+        board.generate_seeded(4, (0, 0), 9);
+        board.open((0, 0));
+        board[(4, 0)].flags = 1;
+        let round_trip: Board = board.to_string().parse().unwrap();
+        assert_eq!(round_trip.to_string(), board.to_string());
+        assert_eq!(round_trip.num_opened(), board.num_opened());
+
+        // squares with several mines or flags are counted below the grid
+        let mut board = Board::with_mines(3, 1, &[(1, 0), (1, 0), (1, 0)]);
+        board.open((0, 0));
+        board[(1, 0)].flags = 2;
+        let text = board.to_string();
+        assert_eq!(text, "3F.\n\n1,0 mines 3 flags 2\n");
+        let round_trip: Board = text.parse().unwrap();
+        let cell = round_trip[(1, 0)];
+        assert_eq!((cell.mines, cell.flags, round_trip.mines_per_square()), (3, 2, 3));
+        assert_eq!(round_trip.to_string(), text);
+    }
+
+    #[test]
+    pub fn text_errors() {
+        let parse = |s: &str| s.parse::<Board>().unwrap_err();
+        assert_eq!(parse("\n  \n"), ParseBoardError {
+            line: 1,
+            column: 1,
+            kind: ParseBoardErrorKind::Empty,
+        });
+        assert_eq!(parse("\n...\n.?.\n"), ParseBoardError {
+            line: 3,
+            column: 2,
+            kind: ParseBoardErrorKind::UnknownSquare('?'),
+        });
+        assert_eq!(parse("...\n..\n"), ParseBoardError {
+            line: 2,
+            column: 3,
+            kind: ParseBoardErrorKind::Width { expected: 3, found: 2 },
+        });
+        assert_eq!(parse("*2.\n...\n"), ParseBoardError {
+            line: 1,
+            column: 2,
+            kind: ParseBoardErrorKind::Number { expected: 1, found: 2 },
+        });
+        // cut short, off the board, not a mine in the grid, or too many
+        let counts = [
+            "0,0 mines 2", "3,0 mines 2 flags 0", "1,0 mines 2 flags 0", "0,0 mines 9 flags 0",
+        ];
+        for counts in counts {
+            assert_eq!(parse(&format!("*2.\n\n{}\n", counts)), ParseBoardError {
+                line: 3,
+                column: 1,
+                kind: ParseBoardErrorKind::Count,
+            });
+        }
+    }
+
+    #[test]
+    pub fn wrap() {
+        let mut board = Board::with_mines(5, 4, &[(4, 0)]);
+        assert_eq!(board[(0, 0)].num, 0);
+        assert_eq!(board.get_neighborhood((0, 0)).count(), 3);
+
+        board.set_wrap("horizontal".parse().unwrap());
+        assert_eq!(board[(0, 0)].num, 1);
+        let mut around: Vec<_> = board.get_neighborhood((0, 0)).collect();
+        around.sort();
+        assert_eq!(around, vec![(0, 1), (1, 0), (1, 1), (4, 0), (4, 1)]);
+
+        board.set_wrap("both".parse().unwrap());
+        assert_eq!(board.get_neighborhood((0, 0)).count(), 8);
+        assert_eq!(board[(0, 3)].num, 1);
+        // the opening reaches round to the squares next to the mine
+        board.open((2, 2));
+        assert!(board[(3, 3)].open && board[(0, 3)].open && board[(4, 1)].open);
+        assert!(board.is_won());
+
+        // too small for eight different neighbours
+        board = Board::new(2, 1);
+        board.set_wrap("both".parse().unwrap());
+        assert_eq!(board.get_neighborhood((0, 0)).collect::<Vec<_>>(), vec![(1, 0)]);
+
+        assert!("sideways".parse::<Wrap>().is_err());
+        assert_eq!(Wrap { horizontal: false, vertical: true }.to_string(), "vertical");
+    }
+
+    #[test]
+    pub fn hex() {
+        let mut board = Board::new(4, 4);
+        board.set_topology("grid hex".parse().unwrap());
+        let around = |board: &Board, p| {
+            let mut around: Vec<_> = board.get_neighborhood(p).collect();
+            around.sort();
+            around
+        };
+        // an unshifted row reaches left, a shifted one right
+        assert_eq!(around(&board, (1, 2)), vec![(0, 1), (0, 2), (0, 3), (1, 1), (1, 3), (2, 2)]);
+        assert_eq!(around(&board, (1, 1)), vec![(0, 1), (1, 0), (1, 2), (2, 0), (2, 1), (2, 2)]);
+        assert_eq!(around(&board, (0, 0)), vec![(0, 1), (1, 0)]);
+        for p in board.squares() {
+            assert!(board.get_neighborhood(p).all(|q| board.get_neighborhood(q).any(|r| r == p)));
+        }
+
+        board.set_topology("grid hex wrap both".parse().unwrap());
+        assert_eq!(around(&board, (0, 0)), vec![(0, 1), (0, 3), (1, 0), (3, 0), (3, 1), (3, 3)]);
+        // the rows wouldn't line up across an odd number of them
+        let mut board = Board::new(4, 3);
+        board.set_topology("grid hex wrap both".parse().unwrap());
+        assert_eq!(board.wrap(), Wrap { horizontal: true, vertical: false });
+
+        board = "..*\n...\n...".parse().unwrap();
+        board.set_topology("grid hex".parse().unwrap());
+        assert_eq!((board[(1, 0)].num, board[(1, 1)].num, board[(2, 1)].num), (1, 1, 1));
+        assert_eq!(board[(0, 1)].num, 0);
+        // the opening stops at the numbers around the mine
+        board.open((0, 2));
+        assert!(board.is_won());
+    }
+
+    #[test]
+    pub fn neighborhoods() {
+        let topology = |neighborhood: &str| Topology {
+            neighborhood: neighborhood.parse().unwrap(),
+            ..Topology::default()
+        };
+        let mut board = Board::with_mines(5, 5, &[(2, 2)]);
+        board.set_topology(topology("knight"));
+        assert_eq!((board[(0, 1)].num, board[(1, 1)].num, board[(4, 3)].num), (1, 0, 1));
+        assert_eq!(board.get_neighborhood((0, 0)).collect::<Vec<_>>(), vec![(2, 1), (1, 2)]);
+
+        // a cross only opens straight across and down
+        board = Board::with_mines(3, 3, &[(1, 1)]);
+        board.set_topology(topology("cross"));
+        assert_eq!(board[(0, 0)].num, 0);
+        board.open((0, 0));
+        assert!(board[(1, 0)].open && board[(0, 1)].open && !board[(2, 2)].open);
+
+        // more than nine mines around, written as #
+        let mines: Vec<_> = (0..5).flat_map(|y| (0..5).map(move |x| (x, y)))
+            .filter(|&p| p != (2, 2))
+            .collect();
+        board = Board::with_mines(5, 5, &mines);
+        board.set_topology(topology("radius2"));
+        assert_eq!(board[(2, 2)].num, 24);
+        board.open((2, 2));
+        let text = board.to_string();
+        assert_eq!(text.lines().nth(2), Some("**#**"));
+        assert_eq!(Board::from_text(&text, topology("radius2")).unwrap().to_string(), text);
+        assert_eq!(
+            Board::from_text(&text, Topology::default()).unwrap_err().kind,
+            ParseBoardErrorKind::FewerThanTen { expected: 8 },
+        );
+
+        // wrapping round a board narrower than the neighbourhood
+        board = Board::new(3, 1);
+        board.set_topology(Topology { wrap: "horizontal".parse().unwrap(), ..topology("radius2") });
+        assert_eq!(board.get_neighborhood((0, 0)).collect::<Vec<_>>(), vec![(1, 0), (2, 0)]);
+
+        let custom = Neighborhood::from_offsets([(0, -2), (3, 0)]).unwrap();
+        assert_eq!(custom.to_string(), "0,-2/3,0");
+        assert_eq!("0,-2/3,0".parse(), Ok(custom));
+        assert_eq!("-1,-1/0,-1/1,-1/-1,0/1,0/-1,1/0,1/1,1".parse(), Ok(Neighborhood::KING));
+        assert_eq!(Neighborhood::KNIGHT.to_string(), "knight");
+        assert!(Neighborhood::from_offsets([(0, 0)]).is_none());
+        assert!("4,0".parse::<Neighborhood>().is_err());
+        assert!("1,x".parse::<Neighborhood>().is_err());
+        let text = "neighborhood 0,-2/3,0 wrap both";
+        assert_eq!(text.parse::<Topology>().unwrap().to_string(), text);
+
+        // hexagons always have their own six
+        board = Board::new(4, 4);
+        board.set_topology(Topology { grid: Grid::Hex, ..topology("knight") });
+        assert_eq!(board.neighborhood(), Neighborhood::KING);
+        assert_eq!(board.get_neighborhood((1, 1)).count(), 6);
+    }
+
+    #[test]
+    pub fn max_mines_per_square() {
+        // big neighbourhoods hold fewer, so numbers stay within two digits
+        let topology = |s: &str| s.parse::<Topology>().unwrap();
+        assert_eq!(Topology::default().max_mines_per_square(), Cell::MAX_MINES);
+        assert_eq!(topology("grid hex").max_mines_per_square(), Cell::MAX_MINES);
+        assert_eq!(topology("neighborhood radius2").max_mines_per_square(), 4);
+
+        let within_3 = Neighborhood::from_offsets(
+            (-3..=3).flat_map(|dy| (-3..=3).map(move |dx| (dx, dy))).filter(|&p| p != (0, 0))
+        ).unwrap();
+        let topology = Topology { neighborhood: within_3, ..Topology::default() };
+        assert_eq!((topology.neighbors(), topology.max_mines_per_square()), (48, 2));
+        let mut board = Board::new(9, 9);
+        board.set_topology(topology);
+        board.set_mines_per_square(Cell::MAX_MINES);
+        assert_eq!(board.mines_per_square(), 2);
+    }
+
+    #[test]
+    pub fn square_at() {
+        let board = Board::new(3, 2);
+        assert_eq!(board.square_at(Board::X + 20, Board::Y + 5), Some((1, 0)));
+        assert_eq!(board.square_at(Board::X + 48, Board::Y + 5), None);
+        assert_eq!(board.square_at(Board::X + 5, Board::Y - 1), None);
+
+        let mut board = Board::new(3, 2);
+        board.set_topology("grid hex".parse().unwrap());
+        assert_eq!(board.pixel_size(), (3*SQ_SIZE + SQ_SIZE/2, SQ_SIZE + SQ_SIZE*3/4));
+        let at = |x, y| board.square_at(Board::X + x, Board::Y + y);
+        // the middles, and the second row pushed over
+        assert_eq!(at(8, 8), Some((0, 0)));
+        assert_eq!(at(16, 20), Some((0, 1)));
+        assert_eq!(at(8, 20), Some((0, 1)));
+        assert_eq!(at(7, 20), None);
+        // either side of the slope between two rows
+        assert_eq!(at(3, 13), Some((0, 0)));
+        assert_eq!(at(3, 14), None);
+        assert_eq!(at(13, 12), Some((0, 0)));
+        assert_eq!(at(13, 13), Some((0, 1)));
+        // the notches along the edges
+        assert_eq!(at(0, 0), None);
+        assert_eq!(at(50, 0), None);
+        assert_eq!(board.square_origin((2, 1)), (Board::X + 40, Board::Y + 12));
+        for p in board.squares() {
+            let (x, y) = board.square_origin(p);
+            assert_eq!(board.square_at(x + 8, y + 8), Some(p));
+        }
+    }
+
+    /// A row of squares, each next to the ones either side of it
+    struct LineBoard(Vec<Cell>);
+    impl MineBoard for LineBoard {
+        type Square = usize;
+
+        fn squares(&self) -> impl Iterator<Item=usize> + use<> {
+            0..self.0.len()
+        }
+
+        fn neighbors(&self, p: usize) -> impl Iterator<Item=usize> + use<> {
+            let len = self.0.len();
+            [p.wrapping_sub(1), p + 1].into_iter().filter(move |&q| q < len)
+        }
+
+        fn cell(&self, p: usize) -> &Cell { &self.0[p] }
+
+        fn cell_mut(&mut self, p: usize) -> &mut Cell { &mut self.0[p] }
+    }
+
+    fn line(len: usize, mines: &[usize]) -> LineBoard {
+        let mut board = LineBoard(vec![Cell::default(); len]);
+        for &p in mines {
+            board.cell_mut(p).mines = 1;
+        }
+        board.compute_numbers();
+        board
+    }
+
+    #[test]
+    pub fn other_shapes() {
+        let mut board = LineBoard(vec![Cell::default(); 8]);
+        board.place_mines(3, &[0], &mut ChaCha8Rng::seed_from_u64(1));
+        assert_eq!(board.num_mines(), 3);
+        assert!(!board.cell(0).is_mine());
+        for p in 0..8 {
+            let around = board.neighbors(p).filter(|&q| board.cell(q).is_mine()).count();
+            assert_eq!(board.cell(p).num as usize, around);
+        }
+
+        // . 1 * 1 1 * 1 .
+        let mut board = line(8, &[2, 5]);
+        assert!(!board.open(0));
+        assert_eq!(board.num_opened(), 2);
+        board.cell_mut(2).flags = 1;
+        assert!(!board.open(3));
+        assert!(!board.chord(3));
+        assert!(board.cell(4).open);
+        assert!(!board.open(7));
+        assert!(board.cell(6).open);
+        assert!(board.is_won());
+
+        // a chord around the wrong flag opens a mine
+        let mut board = line(8, &[2, 5]);
+        board.open(3);
+        board.cell_mut(4).flags = 1;
+        assert!(board.chord(3));
+        assert!(!board.is_won());
+    }
 }
@@ -0,0 +1,105 @@
+use sdl2::rect::Rect;
+
+use crate::*;
+
+/// The face button above the board, which starts a new game
+pub struct FaceButton {
+    rect: Rect,
+    /// Whether the left button was pressed on the face and not released yet
+    held: bool,
+}
+impl FaceButton {
+    pub fn new(rect: Rect) -> Self {
+        Self { rect, held: false }
+    }
+
+    /// Handles the left mouse button going down at `(x, y)`. Returns whether
+    /// the press was on the button.
+    pub fn press(&mut self, x: i32, y: i32) -> bool {
+        self.held = self.contains(x, y);
+        self.held
+    }
+
+    /// Handles the left mouse button coming up at `(x, y)`. The button only
+    /// fires if it was pressed and the mouse is still over it.
+    pub fn release(&mut self, x: i32, y: i32) -> Action {
+        let fired = self.held && self.contains(x, y);
+        self.held = false;
+        if fired { Action::Btn } else { Action::None }
+    }
+
+    pub fn is_held(&self) -> bool { self.held }
+
+    /// The face to show with the mouse at `(x, y)`.
+    pub fn sprite(
+        &self,
+        game_state: &GameState,
+        input_state: &InputState,
+        (x, y): (i32, i32),
+    ) -> Sprite {
+        if self.held && self.contains(x, y) {
+            return Sprite::BtnDown;
+        }
+        match (game_state, input_state) {
+            (GameState::Lose, _) => Sprite::BtnDead,
+            (GameState::Win, _) => Sprite::BtnSunglasses,
+            (_, InputState::Left | InputState::Chord) => Sprite::BtnSurprise,
+            _ => Sprite::BtnSmile,
+        }
+    }
+}
+impl ClickRect for FaceButton {
+    fn click_rect(&self) -> Rect { self.rect }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    pub fn release_over_button() {
+        let mut button = FaceButton::new(rect!(10, 0, BTN_SIZE, BTN_SIZE));
+        assert!(!button.press(0, 0));
+        assert!(matches!(button.release(12, 12), Action::None));
+
+        assert!(button.press(12, 12));
+        assert!(matches!(button.release(12, 12), Action::Btn));
+        assert!(!button.is_held());
+
+        // dragging off the button cancels the click
+        assert!(button.press(12, 12));
+        assert!(matches!(button.release(100, 12), Action::None));
+    }
+
+    #[test]
+    pub fn faces() {
+        let mut button = FaceButton::new(rect!(10, 0, BTN_SIZE, BTN_SIZE));
+        let away = (100, 100);
+        assert!(matches!(
+            button.sprite(&GameState::Play, &InputState::None, away),
+            Sprite::BtnSmile
+        ));
+        assert!(matches!(
+            button.sprite(&GameState::Play, &InputState::Chord, away),
+            Sprite::BtnSurprise
+        ));
+        assert!(matches!(
+            button.sprite(&GameState::Lose, &InputState::Left, away),
+            Sprite::BtnDead
+        ));
+        assert!(matches!(
+            button.sprite(&GameState::Win, &InputState::None, away),
+            Sprite::BtnSunglasses
+        ));
+
+        button.press(12, 12);
+        assert!(matches!(
+            button.sprite(&GameState::Lose, &InputState::None, (12, 12)),
+            Sprite::BtnDown
+        ));
+        assert!(matches!(
+            button.sprite(&GameState::Lose, &InputState::None, away),
+            Sprite::BtnDead
+        ));
+    }
+}
@@ -0,0 +1,282 @@
+use sdl2::keyboard::Keycode;
+use sdl2::pixels::Color;
+use sdl2::rect::Rect;
+use sdl2::render::Canvas;
+use sdl2::video::Window;
+
+use crate::*;
+
+const LABELS: [&str; 3] = ["WIDTH", "HEIGHT", "MINES"];
+const ROW_HEIGHT: usize = COUNTER_HEIGHT + 4;
+const PADDING: usize = 4;
+const LABEL_WIDTH: usize = 6 * ADVANCE;
+/// The distance between the tops of two lines of text
+const ROW_SPACING: usize = GLYPH_HEIGHT + 3;
+
+/// What happened to a dialog after a key press
+pub enum DialogResult<T> {
+    /// The dialog is still open
+    Open,
+    /// The dialog was closed without changing anything
+    Cancel,
+    /// The dialog was closed with `T` picked
+    Done(T),
+}
+
+/// A dialog for picking the width, height and mines of a custom board.
+///
+/// Up and down pick a field, left and right change it (by ten with shift
+/// held), enter accepts and escape cancels.
+pub struct CustomDialog {
+    /// Width, height and mines, in the order of [`LABELS`]
+    values: [usize; 3],
+    selected: usize,
+    error: Option<DifficultyError>,
+}
+impl CustomDialog {
+    /// Opens the dialog with the fields filled in from `current`.
+    pub fn new(current: Difficulty) -> Self {
+        Self {
+            values: [current.width(), current.height(), current.mines()],
+            selected: 0,
+            error: None,
+        }
+    }
+
+    pub fn handle_key(&mut self, keycode: Keycode, shift: bool) -> DialogResult<Difficulty> {
+        let step = if shift { 10 } else { 1 };
+        let max = [Difficulty::MAX_SIDE, Difficulty::MAX_SIDE, 999];
+        match keycode {
+            Keycode::Escape => return DialogResult::Cancel,
+            Keycode::Return | Keycode::KpEnter => {
+                let [width, height, mines] = self.values;
+                match Difficulty::custom(width, height, mines) {
+                    Ok(difficulty) => return DialogResult::Done(difficulty),
+                    Err(e) => {
+                        eprintln!("{}", e);
+                        self.error = Some(e);
+                    },
+                }
+            },
+            Keycode::Up => self.selected = (self.selected + LABELS.len() - 1) % LABELS.len(),
+            Keycode::Down | Keycode::Tab => self.selected = (self.selected + 1) % LABELS.len(),
+            Keycode::Left | Keycode::Minus | Keycode::KpMinus => {
+                let value = &mut self.values[self.selected];
+                *value = value.saturating_sub(step).max(1);
+                self.error = None;
+            },
+            Keycode::Right | Keycode::Plus | Keycode::Equals | Keycode::KpPlus => {
+                let value = &mut self.values[self.selected];
+                *value = (*value + step).min(max[self.selected]);
+                self.error = None;
+            },
+            _ => {},
+        }
+        DialogResult::Open
+    }
+
+    /// Draws the dialog centred in `area`.
+    pub fn render(
+        &self,
+        canvas: &mut Canvas<Window>,
+        spritesheet: &Spritesheet,
+        area: Rect,
+    ) -> Result<(), String> {
+        let width = PADDING + ADVANCE + LABEL_WIDTH + COUNTER_DIGITS*COUNTER_WIDTH + PADDING;
+        let height = PADDING + LABELS.len()*ROW_HEIGHT + GLYPH_HEIGHT + 2*PADDING;
+        let panel = render_panel(canvas, area, width, height)?;
+
+        let black = Color::RGB(0, 0, 0);
+        for (i, label) in LABELS.iter().enumerate() {
+            let y = panel.y() + (PADDING + i*ROW_HEIGHT) as i32;
+            let text_y = y + ((COUNTER_HEIGHT - GLYPH_HEIGHT)/2) as i32;
+            let x = panel.x() + PADDING as i32;
+            if i == self.selected {
+                draw_text(canvas, ">", (x, text_y), 1, black)?;
+            }
+            draw_text(canvas, label, (x + ADVANCE as i32, text_y), 1, black)?;
+            let counter = rect!(
+                x + (ADVANCE + LABEL_WIDTH) as i32,
+                y,
+                COUNTER_DIGITS*COUNTER_WIDTH,
+                COUNTER_HEIGHT
+            );
+            render_counter(canvas, spritesheet, self.values[i] as i64, counter)?;
+        }
+
+        if let Some(error) = &self.error {
+            let message = match error {
+                DifficultyError::Size { .. } => "BAD SIZE",
+                DifficultyError::NoMines => "NO MINES",
+                DifficultyError::TooManyMines { .. } => "TOO MANY MINES",
+                DifficultyError::Parse(_) => "INVALID",
+            };
+            let y = panel.y() + (PADDING + LABELS.len()*ROW_HEIGHT + PADDING) as i32;
+            draw_text(
+                canvas,
+                message,
+                (panel.x() + PADDING as i32, y),
+                1,
+                Color::RGB(192, 0, 0)
+            )?;
+        }
+        Ok(())
+    }
+}
+
+/// A dialog for the name a new record is kept under, filled in with the
+/// last name used.
+///
+/// Letters, digits and spaces are typed and backspace deletes. Enter or
+/// escape keep the record, under [`NameDialog::ANONYMOUS`] if there is no
+/// name.
+pub struct NameDialog {
+    name: String,
+    /// Where the record places, counting from 0
+    place: usize,
+}
+impl NameDialog {
+    pub const ANONYMOUS: &'static str = "ANONYMOUS";
+
+    pub fn new(name: &str, place: usize) -> Self {
+        Self { name: name.chars().take(NAME_LENGTH).collect(), place }
+    }
+
+    pub fn handle_key(&mut self, keycode: Keycode) -> DialogResult<String> {
+        match keycode {
+            Keycode::Return | Keycode::KpEnter | Keycode::Escape => {
+                let name = self.name.trim();
+                let name = if name.is_empty() { Self::ANONYMOUS } else { name };
+                return DialogResult::Done(name.to_string());
+            },
+            Keycode::Backspace => { self.name.pop(); },
+            _ => {
+                let typed = char::from_u32(keycode as u32)
+                    .filter(|c| c.is_ascii_alphanumeric() || *c == ' ');
+                if let (Some(c), true) = (typed, self.name.len() < NAME_LENGTH) {
+                    self.name.push(c.to_ascii_uppercase());
+                }
+            },
+        }
+        DialogResult::Open
+    }
+
+    /// Draws the dialog centred in `area`.
+    pub fn render(&self, canvas: &mut Canvas<Window>, area: Rect) -> Result<(), String> {
+        let title = format!("NEW RECORD #{}", self.place + 1);
+        let width = PADDING + title.len().max(NAME_LENGTH + 1)*ADVANCE + PADDING;
+        let height = PADDING + 3*ROW_SPACING + PADDING;
+        let panel = render_panel(canvas, area, width, height)?;
+
+        let black = Color::RGB(0, 0, 0);
+        let x = panel.x() + PADDING as i32;
+        let y = |row: usize| panel.y() + (PADDING + row*ROW_SPACING) as i32;
+        draw_text(canvas, &title, (x, y(0)), 1, Color::RGB(192, 0, 0))?;
+        draw_text(canvas, "NAME:", (x, y(1)), 1, black)?;
+        draw_text(canvas, &format!("{}_", self.name), (x, y(2)), 1, black)
+    }
+}
+
+/// The best times, for one difficulty at a time.
+///
+/// Left and right go through the difficulties, up and down pick a win to
+/// show more of, and escape or enter close it.
+pub struct ScoresDialog {
+    /// Every difficulty with wins, and the one being played
+    difficulties: Vec<Difficulty>,
+    /// The index of the difficulty shown
+    shown: usize,
+    /// The place of the win shown in full
+    selected: usize,
+}
+impl ScoresDialog {
+    /// Opens the dialog on `difficulty` with the win at `place` picked.
+    pub fn new(scoreboard: &Scoreboard, difficulty: Difficulty, place: usize) -> Self {
+        let mut difficulties = scoreboard.difficulties();
+        if !difficulties.contains(&difficulty) {
+            difficulties.insert(0, difficulty);
+        }
+        let shown = difficulties.iter().position(|d| *d == difficulty).unwrap_or(0);
+        Self { difficulties, shown, selected: place }
+    }
+
+    pub fn handle_key(&mut self, keycode: Keycode, scoreboard: &Scoreboard) -> DialogResult<()> {
+        let count = self.difficulties.len();
+        let wins = scoreboard.best(self.difficulties[self.shown]).count();
+        match keycode {
+            Keycode::Escape | Keycode::Return | Keycode::KpEnter => return DialogResult::Cancel,
+            Keycode::Left => {
+                self.shown = (self.shown + count - 1) % count;
+                self.selected = 0;
+            },
+            Keycode::Right => {
+                self.shown = (self.shown + 1) % count;
+                self.selected = 0;
+            },
+            Keycode::Up => self.selected = self.selected.saturating_sub(1),
+            Keycode::Down => self.selected = (self.selected + 1).min(wins.saturating_sub(1)),
+            _ => {},
+        }
+        DialogResult::Open
+    }
+
+    /// Draws the dialog centred in `area`.
+    pub fn render(
+        &self,
+        canvas: &mut Canvas<Window>,
+        scoreboard: &Scoreboard,
+        area: Rect,
+    ) -> Result<(), String> {
+        // a place, a name and a time
+        let columns = 2 + 1 + NAME_LENGTH + 1 + 6;
+        let width = PADDING + columns*ADVANCE + PADDING;
+        let height = PADDING + (SCORES_KEPT + 5)*ROW_SPACING + PADDING;
+        let panel = render_panel(canvas, area, width, height)?;
+
+        let black = Color::RGB(0, 0, 0);
+        let red = Color::RGB(192, 0, 0);
+        let x = panel.x() + PADDING as i32;
+        let y = |row: usize| panel.y() + (PADDING + row*ROW_SPACING) as i32;
+
+        let difficulty = self.difficulties[self.shown];
+        let title = difficulty.to_string().to_uppercase();
+        draw_text(canvas, &title, (x, y(0)), 1, black)?;
+        if self.difficulties.len() > 1 {
+            let arrows_x = panel.right() - (PADDING + text_width("<>", 1)) as i32;
+            draw_text(canvas, "<>", (arrows_x, y(0)), 1, black)?;
+        }
+
+        let wins: Vec<_> = scoreboard.best(difficulty).collect();
+        if wins.is_empty() {
+            return draw_text(canvas, "NO WINS YET", (x, y(2)), 1, black);
+        }
+        for (place, score) in wins.iter().enumerate() {
+            let line = format!(
+                "{:>2} {:<name$.name$} {:>6.2}",
+                place + 1, score.name, score.time.as_secs_f64(),
+                name = NAME_LENGTH,
+            );
+            let color = if place == self.selected { red } else { black };
+            draw_text(canvas, &line, (x, y(1 + place)), 1, color)?;
+        }
+
+        let Some(score) = wins.get(self.selected) else { return Ok(()) };
+        let details = [
+            format!("3BV {} EFF {}%", score.bbbv, score.efficiency()),
+            score.day(),
+            format!(
+                "{} {}",
+                if score.no_guess { "NO GUESS" } else { "RANDOM" },
+                match score.hints {
+                    0 => "NO HINTS".to_string(),
+                    1 => "1 HINT".to_string(),
+                    hints => format!("{} HINTS", hints),
+                },
+            ),
+        ];
+        for (i, line) in details.iter().enumerate() {
+            draw_text(canvas, line, (x, y(SCORES_KEPT + 2 + i)), 1, black)?;
+        }
+        Ok(())
+    }
+}
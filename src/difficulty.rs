@@ -0,0 +1,174 @@
+use std::fmt;
+use std::str::FromStr;
+
+/// The size of the board and how many mines are on it
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+pub enum Difficulty {
+    Beginner,
+    Intermediate,
+    #[default]
+    Expert,
+    Custom { width: usize, height: usize, mines: usize },
+}
+
+/// Why a custom difficulty was rejected
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum DifficultyError {
+    /// The board is narrower or shorter than one square, or too big
+    Size { width: usize, height: usize },
+    /// There are no mines
+    NoMines,
+    /// There isn't room for the mines and a safe first click
+    TooManyMines { mines: usize, cells: usize },
+    /// A custom difficulty couldn't be read
+    Parse(String),
+}
+impl fmt::Display for DifficultyError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            DifficultyError::Size { width, height } => write!(
+                f, "a {}x{} board is not allowed, width and height must be between 1 and {}",
+                width, height, Difficulty::MAX_SIDE
+            ),
+            DifficultyError::NoMines => write!(f, "there must be at least one mine"),
+            DifficultyError::TooManyMines { mines, cells } => write!(
+                f, "{} mines don't fit on {} squares, there can be at most {}",
+                mines, cells, cells.saturating_sub(1)
+            ),
+            DifficultyError::Parse(s) => write!(
+                f, "{:?} is not a difficulty, expected beginner, intermediate, expert \
+                    or WIDTHxHEIGHTxMINES", s
+            ),
+        }
+    }
+}
+impl std::error::Error for DifficultyError {}
+
+impl Difficulty {
+    /// The longest a side of a custom board may be
+    pub const MAX_SIDE: usize = 99;
+
+    /// Checks that `mines` fit on a `width` by `height` board with at least
+    /// one square left over for the first click.
+    pub fn custom(width: usize, height: usize, mines: usize) -> Result<Self, DifficultyError> {
+        if !(1..=Self::MAX_SIDE).contains(&width) || !(1..=Self::MAX_SIDE).contains(&height) {
+            return Err(DifficultyError::Size { width, height });
+        }
+        if mines == 0 {
+            return Err(DifficultyError::NoMines);
+        }
+        if mines >= width * height {
+            return Err(DifficultyError::TooManyMines { mines, cells: width * height });
+        }
+        Ok(Difficulty::Custom { width, height, mines })
+    }
+
+    pub fn width(&self) -> usize {
+        match self {
+            Difficulty::Beginner => 9,
+            Difficulty::Intermediate => 16,
+            Difficulty::Expert => 30,
+            Difficulty::Custom { width, .. } => *width,
+        }
+    }
+
+    pub fn height(&self) -> usize {
+        match self {
+            Difficulty::Beginner => 9,
+            Difficulty::Intermediate => 16,
+            Difficulty::Expert => 16,
+            Difficulty::Custom { height, .. } => *height,
+        }
+    }
+
+    pub fn mines(&self) -> usize {
+        match self {
+            Difficulty::Beginner => 10,
+            Difficulty::Intermediate => 40,
+            Difficulty::Expert => 99,
+            Difficulty::Custom { mines, .. } => *mines,
+        }
+    }
+}
+impl fmt::Display for Difficulty {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Difficulty::Beginner => write!(f, "beginner"),
+            Difficulty::Intermediate => write!(f, "intermediate"),
+            Difficulty::Expert => write!(f, "expert"),
+            Difficulty::Custom { width, height, mines } => write!(
+                f, "{}x{}x{}", width, height, mines
+            ),
+        }
+    }
+}
+impl FromStr for Difficulty {
+    type Err = DifficultyError;
+
+    /// Reads a preset name or a custom `WIDTHxHEIGHTxMINES`.
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "beginner" => return Ok(Difficulty::Beginner),
+            "intermediate" => return Ok(Difficulty::Intermediate),
+            "expert" => return Ok(Difficulty::Expert),
+            _ => {},
+        }
+        let parts: Vec<usize> = s.split('x')
+            .map(str::parse)
+            .collect::<Result<_, _>>()
+            .map_err(|_| DifficultyError::Parse(s.to_string()))?;
+        match parts[..] {
+            [width, height, mines] => Difficulty::custom(width, height, mines),
+            _ => Err(DifficultyError::Parse(s.to_string())),
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    pub fn presets() {
+        let sizes: Vec<_> = [Difficulty::Beginner, Difficulty::Intermediate, Difficulty::Expert]
+            .iter()
+            .map(|d| (d.width(), d.height(), d.mines()))
+            .collect();
+        assert_eq!(sizes, vec![(9, 9, 10), (16, 16, 40), (30, 16, 99)]);
+    }
+
+    #[test]
+    pub fn custom() {
+        assert_eq!(
+            Difficulty::custom(8, 4, 31),
+            Ok(Difficulty::Custom { width: 8, height: 4, mines: 31 }),
+        );
+        assert_eq!(
+            Difficulty::custom(8, 4, 32),
+            Err(DifficultyError::TooManyMines { mines: 32, cells: 32 }),
+        );
+        assert_eq!(Difficulty::custom(8, 4, 0), Err(DifficultyError::NoMines));
+        assert_eq!(
+            Difficulty::custom(0, 4, 1),
+            Err(DifficultyError::Size { width: 0, height: 4 }),
+        );
+    }
+
+    #[test]
+    pub fn parse() {
+        assert_eq!("expert".parse(), Ok(Difficulty::Expert));
+        assert_eq!(
+            "20x10x30".parse(),
+            Ok(Difficulty::Custom { width: 20, height: 10, mines: 30 }),
+        );
+        assert_eq!(
+            "2x2x4".parse::<Difficulty>(),
+            Err(DifficultyError::TooManyMines { mines: 4, cells: 4 }),
+        );
+        assert!(matches!("20x10".parse::<Difficulty>(), Err(DifficultyError::Parse(_))));
+        assert!(matches!("hard".parse::<Difficulty>(), Err(DifficultyError::Parse(_))));
+
+        let custom = Difficulty::custom(20, 10, 30).unwrap();
+        assert_eq!(custom.to_string().parse(), Ok(custom));
+    }
+}
@@ -0,0 +1,438 @@
+use crate::*;
+use crate::solver::Hint;
+
+/// How boards are generated for a game
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Settings {
+    pub difficulty: Difficulty,
+    /// Generate every board from this seed instead of a random one
+    pub seed: Option<u64>,
+    /// Only generate boards that can be cleared without guessing, which
+    /// needs one mine per square
+    pub no_guess: bool,
+    /// Which squares of the board are next to each other
+    pub topology: Topology,
+    /// The most mines a single square can hold
+    pub mines_per_square: u8,
+}
+impl Default for Settings {
+    fn default() -> Self {
+        Self {
+            difficulty: Difficulty::default(),
+            seed: None,
+            no_guess: false,
+            topology: Topology::default(),
+            mines_per_square: 1,
+        }
+    }
+}
+
+/// How many of each kind of click were made on the board
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct Clicks {
+    pub open: usize,
+    pub flag: usize,
+    pub chord: usize,
+}
+impl Clicks {
+    pub fn total(&self) -> usize {
+        self.open + self.flag + self.chord
+    }
+}
+
+/// Something that changed as the result of an [`Action`]
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum GameEvent {
+    /// The board was generated, from `seed` if it was seeded
+    Started { seed: Option<u64> },
+    /// No-guess generation failed, so an ordinary board was generated
+    NoGuessFailed(GenerateError),
+    /// These squares were opened
+    Opened(Vec<(usize, usize)>),
+    /// The flags on a square changed, to `flags` of them
+    Flagged { square: (usize, usize), flags: u8 },
+    /// A mine was opened
+    Lost { at: (usize, usize) },
+    /// Every safe square is open
+    Won,
+    /// The board was cleared for a new game
+    Reset,
+}
+
+/// A game of Minesweeper: the board and the rules for playing on it
+pub struct Game {
+    settings: Settings,
+    board: Board,
+    state: GameState,
+    timer: Timer,
+    clicks: Clicks,
+    hints_used: usize,
+    /// Mines to place instead of generating them, for playing back replays
+    layout: Option<Vec<(usize, usize)>>,
+}
+impl Game {
+    pub fn new(settings: Settings) -> Self {
+        Self {
+            settings,
+            board: empty_board(&settings),
+            state: GameState::Init,
+            timer: Timer::new(),
+            clicks: Clicks::default(),
+            hints_used: 0,
+            layout: None,
+        }
+    }
+
+    /// A game whose first click always finds mines on exactly the squares
+    /// in `mines`, as in a replay. A square listed more than once holds
+    /// that many mines.
+    pub fn with_mines(
+        width: usize,
+        height: usize,
+        topology: Topology,
+        mines_per_square: u8,
+        mines: Vec<(usize, usize)>,
+    ) -> Self {
+        let difficulty = Difficulty::Custom { width, height, mines: mines.len() };
+        let settings = Settings { difficulty, topology, mines_per_square, ..Settings::default() };
+        let mut game = Self::new(settings);
+        game.layout = Some(mines);
+        game
+    }
+
+    /// Picks up a game that was saved part way through.
+    pub fn resume(saved: SavedGame) -> Self {
+        let mut timer = Timer::new();
+        timer.start_from(saved.elapsed);
+        Self {
+            settings: saved.settings,
+            board: saved.board,
+            state: GameState::Play,
+            timer,
+            clicks: saved.clicks,
+            hints_used: saved.hints_used,
+            layout: None,
+        }
+    }
+
+    pub fn settings(&self) -> &Settings { &self.settings }
+
+    pub fn difficulty(&self) -> Difficulty { self.settings.difficulty }
+
+    pub fn board(&self) -> &Board { &self.board }
+
+    pub fn state(&self) -> &GameState { &self.state }
+
+    pub fn timer(&self) -> &Timer { &self.timer }
+
+    pub fn clicks(&self) -> &Clicks { &self.clicks }
+
+    pub fn hints_used(&self) -> usize { self.hints_used }
+
+    /// Mines minus flags, which goes negative with too many flags.
+    pub fn mines_left(&self) -> i64 {
+        self.difficulty().mines() as i64 - self.board.num_flags() as i64
+    }
+
+    /// Starts over with a board for `difficulty`.
+    pub fn set_difficulty(&mut self, difficulty: Difficulty) {
+        self.settings.difficulty = difficulty;
+        self.layout = None;
+        self.board = empty_board(&self.settings);
+        self.reset();
+    }
+
+    /// Clears the board for a new game with the same settings.
+    pub fn reset(&mut self) {
+        self.board.reset();
+        self.state = GameState::Init;
+        self.timer.reset();
+        self.clicks = Clicks::default();
+        self.hints_used = 0;
+    }
+
+    /// Whether the solver can give hints and probabilities for this game,
+    /// which it can't when squares hold several mines.
+    pub fn supports_hints(&self) -> bool {
+        self.settings.mines_per_square == 1
+    }
+
+    /// Suggests a move, counting it against the game. Only gives hints
+    /// while the game is being played.
+    pub fn hint(&mut self) -> Option<Hint> {
+        if !matches!(self.state, GameState::Play) {
+            return None;
+        }
+        let hint = solver::hint(&self.board)?;
+        self.hints_used += 1;
+        Some(hint)
+    }
+
+    /// Plays `action`, returning everything that changed because of it.
+    pub fn apply(&mut self, action: Action) -> Vec<GameEvent> {
+        let mut events = vec![];
+        match (&self.state, action) {
+            (GameState::Init, Action::Open(x, y)) => {
+                self.state = GameState::Play;
+                self.timer.start();
+                self.clicks.open += 1;
+                self.generate((x, y), &mut events);
+                self.open_with(&mut events, |board| board.open((x, y)));
+            },
+            (GameState::Play, Action::Open(x, y)) => {
+                self.clicks.open += 1;
+                self.open_with(&mut events, |board| board.open((x, y)));
+            },
+            (GameState::Play, Action::Chord(x, y)) => {
+                self.clicks.chord += 1;
+                self.open_with(&mut events, |board| board.chord((x, y)));
+            },
+            (GameState::Play, Action::Flag(x, y)) => {
+                self.clicks.flag += 1;
+                if !self.board[(x, y)].open {
+                    let flags = self.board.cycle_flags((x, y));
+                    events.push(GameEvent::Flagged { square: (x, y), flags });
+                }
+            },
+            (GameState::Play | GameState::Win | GameState::Lose, Action::Btn) => {
+                self.reset();
+                events.push(GameEvent::Reset);
+            },
+            (_, _) => {}
+        }
+
+        if let GameState::Play = self.state {
+            if self.board.is_won() {
+                self.state = GameState::Win;
+                self.board.flag_mines();
+                events.push(GameEvent::Won);
+            }
+        }
+        if let GameState::Win | GameState::Lose = self.state {
+            self.timer.stop();
+        }
+        events
+    }
+
+    /// Places the mines for a first click at `p`.
+    fn generate(&mut self, p: (usize, usize), events: &mut Vec<GameEvent>) {
+        if let Some(mines) = &self.layout {
+            self.board = Board::with_mines(self.board.width(), self.board.height(), mines);
+            self.board.set_topology(self.settings.topology);
+            let most = self.board.mines_per_square().max(self.settings.mines_per_square);
+            self.board.set_mines_per_square(most);
+            events.push(GameEvent::Started { seed: None });
+            return;
+        }
+        let mines = self.difficulty().mines();
+        if self.settings.no_guess {
+            let result = match self.settings.seed {
+                Some(seed) => self.board.generate_no_guess_seeded(mines, p, seed),
+                None => self.board.generate_no_guess(mines, p),
+            };
+            match result {
+                Ok(()) => {
+                    events.push(GameEvent::Started { seed: self.board.seed() });
+                    return;
+                },
+                Err(e) => events.push(GameEvent::NoGuessFailed(e)),
+            }
+        }
+        match self.settings.seed {
+            Some(seed) => self.board.generate_seeded(mines, p, seed),
+            None => self.board.generate(mines, p),
+        }
+        events.push(GameEvent::Started { seed: self.board.seed() });
+    }
+
+    /// Runs `open` on the board, reporting the squares it opened and
+    /// whether it lost the game.
+    fn open_with(
+        &mut self,
+        events: &mut Vec<GameEvent>,
+        open: impl FnOnce(&mut Board) -> bool,
+    ) {
+        let squares = || (0..self.board.height())
+            .flat_map(|y| (0..self.board.width()).map(move |x| (x, y)));
+        let closed: Vec<_> = squares().filter(|&p| !self.board[p].open).collect();
+
+        let lost = open(&mut self.board);
+
+        let opened: Vec<_> = closed.into_iter().filter(|&p| self.board[p].open).collect();
+        let exploded = opened.iter().copied().find(|&p| self.board[p].is_mine());
+        if !opened.is_empty() {
+            events.push(GameEvent::Opened(opened));
+        }
+        if lost {
+            self.state = GameState::Lose;
+            if let Some(at) = exploded {
+                events.push(GameEvent::Lost { at });
+            }
+        }
+    }
+}
+
+/// A board for `settings` with no mines on it yet.
+fn empty_board(settings: &Settings) -> Board {
+    let difficulty = settings.difficulty;
+    let mut board = Board::new(difficulty.width(), difficulty.height());
+    board.set_topology(settings.topology);
+    board.set_mines_per_square(settings.mines_per_square);
+    board
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn seeded(seed: u64) -> Game {
+        Game::new(Settings {
+            difficulty: Difficulty::Beginner,
+            seed: Some(seed),
+            ..Settings::default()
+        })
+    }
+
+    fn find(game: &Game, mine: bool) -> (usize, usize) {
+        (0..game.board().height())
+            .flat_map(|y| (0..game.board().width()).map(move |x| (x, y)))
+            .find(|&p| game.board()[p].is_mine() == mine && !game.board()[p].open)
+            .unwrap()
+    }
+
+    #[test]
+    pub fn first_click() {
+        let mut game = seeded(3);
+        let events = game.apply(Action::Open(4, 4));
+        assert_eq!(events[0], GameEvent::Started { seed: Some(3) });
+        assert!(matches!(&events[1], GameEvent::Opened(squares) if squares.contains(&(4, 4))));
+        assert!(matches!(game.state(), GameState::Play));
+        assert!(game.timer().is_running());
+        assert_eq!(game.board().num_mines(), 10);
+    }
+
+    #[test]
+    pub fn lose() {
+        let mut game = seeded(3);
+        game.apply(Action::Open(4, 4));
+        let mine = find(&game, true);
+        let events = game.apply(Action::Open(mine.0, mine.1));
+        assert_eq!(events, vec![
+            GameEvent::Opened(vec![mine]),
+            GameEvent::Lost { at: mine },
+        ]);
+        assert!(matches!(game.state(), GameState::Lose));
+        assert!(!game.timer().is_running());
+
+        // nothing but the button does anything once the game is over
+        let safe = find(&game, false);
+        assert!(game.apply(Action::Open(safe.0, safe.1)).is_empty());
+        assert!(game.apply(Action::Flag(safe.0, safe.1)).is_empty());
+        assert_eq!(game.apply(Action::Btn), vec![GameEvent::Reset]);
+        assert!(matches!(game.state(), GameState::Init));
+    }
+
+    #[test]
+    pub fn flag() {
+        let mut game = seeded(3);
+        game.apply(Action::Open(4, 4));
+        let p = find(&game, true);
+        assert_eq!(
+            game.apply(Action::Flag(p.0, p.1)),
+            vec![GameEvent::Flagged { square: p, flags: 1 }],
+        );
+        assert_eq!(game.mines_left(), 9);
+        assert_eq!(
+            game.apply(Action::Flag(p.0, p.1)),
+            vec![GameEvent::Flagged { square: p, flags: 0 }],
+        );
+        assert_eq!(game.mines_left(), 10);
+        assert_eq!(game.clicks().flag, 2);
+    }
+
+    #[test]
+    pub fn win() {
+        let mut game = seeded(3);
+        game.apply(Action::Open(4, 4));
+        let mut events = vec![];
+        while matches!(game.state(), GameState::Play) {
+            let p = find(&game, false);
+            events = game.apply(Action::Open(p.0, p.1));
+        }
+        assert_eq!(events.last(), Some(&GameEvent::Won));
+        assert!(matches!(game.state(), GameState::Win));
+        assert_eq!(game.mines_left(), 0);
+        assert!(!game.timer().is_running());
+    }
+
+    #[test]
+    pub fn hints() {
+        let mut game = seeded(3);
+        assert!(game.hint().is_none());
+        game.apply(Action::Open(4, 4));
+        assert!(game.hint().is_some());
+        assert_eq!(game.hints_used(), 1);
+        game.apply(Action::Btn);
+        assert_eq!(game.hints_used(), 0);
+
+        // the solver only knows single mines
+        assert!(game.supports_hints());
+        let mut game = Game::new(Settings { mines_per_square: 2, ..Settings::default() });
+        game.apply(Action::Open(4, 4));
+        assert!(!game.supports_hints());
+        assert!(game.hint().is_none());
+        assert_eq!(game.hints_used(), 0);
+    }
+
+    #[test]
+    pub fn with_mines() {
+        let mut game = Game::with_mines(3, 3, Topology::default(), 1, vec![(0, 0), (2, 2)]);
+        for _ in 0..2 {
+            game.apply(Action::Open(1, 1));
+            assert_eq!(game.board()[(1, 1)].num, 2);
+            assert!(game.board()[(0, 0)].is_mine() && game.board()[(2, 2)].is_mine());
+            assert_eq!(game.mines_left(), 2);
+            game.apply(Action::Btn);
+        }
+    }
+
+    #[test]
+    pub fn several_mines() {
+        let mut game = Game::with_mines(3, 1, Topology::default(), 3, vec![(1, 0), (1, 0)]);
+        game.apply(Action::Open(0, 0));
+        assert_eq!(game.board().mines_per_square(), 3);
+        assert_eq!(game.mines_left(), 2);
+        // the flags go up to three before coming off
+        for flags in [1, 2, 3, 0] {
+            assert_eq!(
+                game.apply(Action::Flag(1, 0)),
+                vec![GameEvent::Flagged { square: (1, 0), flags }],
+            );
+        }
+
+        game.apply(Action::Open(2, 0));
+        assert!(matches!(game.state(), GameState::Win));
+        assert_eq!((game.board()[(1, 0)].flags, game.mines_left()), (2, 0));
+
+        // the solver only knows single mines, so no-guess says it failed
+        let mut game = Game::new(Settings {
+            no_guess: true,
+            mines_per_square: 2,
+            ..Settings::default()
+        });
+        let events = game.apply(Action::Open(4, 4));
+        assert_eq!(events[0], GameEvent::NoGuessFailed(GenerateError::SeveralMinesPerSquare));
+        assert!(matches!(events[1], GameEvent::Started { .. }));
+        assert!(game.board().squares().all(|p| game.board()[p].mines <= 2));
+    }
+
+    #[test]
+    pub fn set_difficulty() {
+        let mut game = seeded(3);
+        game.apply(Action::Open(4, 4));
+        game.set_difficulty(Difficulty::Expert);
+        assert!(matches!(game.state(), GameState::Init));
+        assert_eq!((game.board().width(), game.board().height()), (30, 16));
+        assert_eq!(game.mines_left(), 99);
+    }
+}
@@ -0,0 +1,174 @@
+//! Hexagonal squares, drawn from scratch since the spritesheet only has
+//! square ones.
+//!
+//! A hexagon fills the same box as a square, with its points at the top and
+//! bottom. Everything is drawn a row of pixels at a time, from where each
+//! row of the hexagon starts and ends.
+
+use sdl2::pixels::Color;
+use sdl2::rect::{ Point, Rect };
+use sdl2::render::Canvas;
+use sdl2::video::Window;
+
+use crate::*;
+
+const LIGHT: Color = Color::RGB(255, 255, 255);
+const FACE: Color = Color::RGB(192, 192, 192);
+const SHADOW: Color = Color::RGB(128, 128, 128);
+const BLACK: Color = Color::RGB(0, 0, 0);
+const RED: Color = Color::RGB(255, 0, 0);
+
+/// The first and last column of `row` in a hexagon shrunk by `inset`
+/// pixels all round, counting from the top left of its box.
+fn span(row: i32, inset: i32) -> Option<(i32, i32)> {
+    // in half pixels from the middle of the box
+    let half = SQ_I32 - 2*inset;
+    let dy = (2*row + 1 - SQ_I32).abs();
+    let dx = half.min(2*(half - dy));
+    if dy > half || dx <= 0 {
+        return None;
+    }
+    let first = (SQ_I32 - dx)/2;
+    Some((first, SQ_I32 - 1 - first))
+}
+
+/// The pixels around the edge of a hexagon shrunk by `inset`, counting from
+/// the top left of its box.
+fn edge(inset: i32) -> Vec<(i32, i32)> {
+    let mut pixels = vec![];
+    for row in 0..SQ_I32 {
+        let Some((first, last)) = span(row, inset) else { continue };
+        let (above, below) = (span(row - 1, inset), span(row + 1, inset));
+        // past the end of either row next to it, or at the end of its own
+        let outside = |span: Option<(i32, i32)>, x: i32| {
+            span.is_none_or(|(first, last)| x < first || x > last)
+        };
+        pixels.extend((first..=last)
+            .filter(|&x| x == first || x == last || outside(above, x) || outside(below, x))
+            .map(|x| (x, row)));
+    }
+    pixels
+}
+
+/// Fills the hexagon in `rect` with the current draw colour.
+pub fn fill_hex(canvas: &mut Canvas<Window>, rect: Rect) -> Result<(), String> {
+    for row in 0..SQ_I32 {
+        if let Some((first, last)) = span(row, 0) {
+            let y = rect.y() + row;
+            canvas.draw_line((rect.x() + first, y), (rect.x() + last, y))?;
+        }
+    }
+    Ok(())
+}
+
+/// Draws a two pixel outline just inside the hexagon in `rect`.
+pub fn outline_hex(canvas: &mut Canvas<Window>, rect: Rect, c: Color) -> Result<(), String> {
+    let color = canvas.draw_color();
+    canvas.set_draw_color(c);
+    let points: Vec<_> = edge(0).into_iter().chain(edge(1))
+        .map(|(x, y)| Point::new(rect.x() + x, rect.y() + y))
+        .collect();
+    canvas.draw_points(&points[..])?;
+    canvas.set_draw_color(color);
+    Ok(())
+}
+
+/// Draws `sprite` as a hexagon in `rect`.
+pub fn draw_hex(canvas: &mut Canvas<Window>, sprite: Sprite, rect: Rect) -> Result<(), String> {
+    let color = canvas.draw_color();
+    let (x, y) = (rect.x(), rect.y());
+    match sprite {
+        Sprite::Closed | Sprite::Flag => {
+            canvas.set_draw_color(FACE);
+            fill_hex(canvas, rect)?;
+            // lit from the top left
+            let (lit, shaded): (Vec<_>, Vec<_>) = edge(0).into_iter()
+                .chain(edge(1))
+                .map(|(dx, dy)| Point::new(x + dx, y + dy))
+                .partition(|p| (p.x() - x) + (p.y() - y) < SQ_I32 - 1);
+            canvas.set_draw_color(LIGHT);
+            canvas.draw_points(&lit[..])?;
+            canvas.set_draw_color(SHADOW);
+            canvas.draw_points(&shaded[..])?;
+        },
+        _ => {
+            canvas.set_draw_color(if matches!(sprite, Sprite::MineRed) { RED } else { FACE });
+            fill_hex(canvas, rect)?;
+            outline(canvas, rect, SHADOW)?;
+        },
+    }
+
+    match sprite {
+        Sprite::Flag => flag(canvas, rect)?,
+        Sprite::Mine | Sprite::MineRed => mine(canvas, rect)?,
+        Sprite::MineCross => {
+            mine(canvas, rect)?;
+            canvas.set_draw_color(RED);
+            canvas.draw_line((x + 3, y + 3), (x + 12, y + 12))?;
+            canvas.draw_line((x + 12, y + 3), (x + 3, y + 12))?;
+        },
+        Sprite::Num(n) if n > 0 => draw_number(canvas, n, rect)?,
+        _ => {},
+    }
+    canvas.set_draw_color(color);
+    Ok(())
+}
+
+/// A one pixel outline of the hexagon in `rect`.
+fn outline(canvas: &mut Canvas<Window>, rect: Rect, c: Color) -> Result<(), String> {
+    canvas.set_draw_color(c);
+    let points: Vec<_> = edge(0).into_iter()
+        .map(|(x, y)| Point::new(rect.x() + x, rect.y() + y))
+        .collect();
+    canvas.draw_points(&points[..])
+}
+
+fn mine(canvas: &mut Canvas<Window>, rect: Rect) -> Result<(), String> {
+    let (x, y) = (rect.x(), rect.y());
+    canvas.set_draw_color(BLACK);
+    canvas.fill_rects(&[
+        rect!(x + 5, y + 5, 6, 6),
+        rect!(x + 3, y + 7, 10, 2),
+        rect!(x + 7, y + 3, 2, 10),
+    ])?;
+    canvas.set_draw_color(LIGHT);
+    canvas.draw_point((x + 6, y + 6))
+}
+
+fn flag(canvas: &mut Canvas<Window>, rect: Rect) -> Result<(), String> {
+    let (x, y) = (rect.x(), rect.y());
+    canvas.set_draw_color(RED);
+    for (row, width) in [1, 3, 5, 3, 1].into_iter().enumerate() {
+        let row = y + 3 + row as i32;
+        canvas.draw_line((x + 8 - width, row), (x + 7, row))?;
+    }
+    canvas.set_draw_color(BLACK);
+    canvas.fill_rects(&[
+        rect!(x + 8, y + 3, 1, 8),
+        rect!(x + 6, y + 10, 5, 1),
+        rect!(x + 4, y + 11, 9, 2),
+    ])
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    pub fn span() {
+        // the points, and the sides between them
+        assert_eq!(super::span(0, 0), Some((7, 8)));
+        assert_eq!(super::span(4, 0), Some((0, 15)));
+        assert_eq!(super::span(11, 0), Some((0, 15)));
+        assert_eq!(super::span(15, 0), Some((7, 8)));
+        assert_eq!(super::span(-1, 0), None);
+        assert_eq!(super::span(16, 0), None);
+        for row in 0..SQ_I32 {
+            assert_eq!(super::span(row, 0), super::span(SQ_I32 - 1 - row, 0));
+        }
+        // shrunk, the hexagon sits inside the first
+        assert_eq!(super::span(0, 1), None);
+        assert_eq!(super::span(1, 1), Some((7, 8)));
+        assert_eq!(super::span(5, 1), Some((1, 14)));
+    }
+}
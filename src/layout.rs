@@ -0,0 +1,103 @@
+use sdl2::rect::Rect;
+
+use crate::*;
+
+pub const COUNTER_DIGITS: usize = 3;
+pub const COUNTER_WIDTH: usize = 13;
+pub const COUNTER_HEIGHT: usize = 23;
+
+/// Space left between the counters and the edges of the window
+const MARGIN: usize = 2;
+
+/// Where everything goes in the window for a board of a given size
+#[derive(Debug, Clone, Copy)]
+pub struct Layout {
+    pub window_width: u32,
+    pub window_height: u32,
+    pub board: Rect,
+    pub counter: Rect,
+    pub button: Rect,
+    pub timer: Rect,
+}
+impl Layout {
+    /// The narrowest the top bar can be with both counters and the button.
+    pub const MIN_WIDTH: usize = 2*(MARGIN + COUNTER_DIGITS*COUNTER_WIDTH) + 2*MARGIN + BTN_SIZE;
+
+    /// Lays out the window for a board `width` by `height` squares. The top
+    /// bar is never narrower than [`Layout::MIN_WIDTH`], even when the board
+    /// is.
+    pub fn new(width: usize, height: usize) -> Self {
+        Self::with_board_pixels(width*SQ_SIZE, height*SQ_SIZE)
+    }
+
+    /// Lays out the window for `board`, leaving room past its right and
+    /// bottom edges for the squares they wrap around to.
+    pub fn for_board(board: &Board) -> Self {
+        let wrap = board.wrap();
+        let (width, height) = board.grid().span(
+            board.width() + wrap.horizontal as usize,
+            board.height() + wrap.vertical as usize,
+        );
+        let mut layout = Self::with_board_pixels(width, height);
+        layout.board = board.click_rect();
+        layout
+    }
+
+    /// Lays out the window for a board `width` by `height` pixels.
+    fn with_board_pixels(width: usize, height: usize) -> Self {
+        let board = rect!(BOARD_X, BOARD_Y, width, height);
+        let window_width = (BOARD_X as usize + width).max(Self::MIN_WIDTH);
+        let window_height = BOARD_Y as usize + height;
+
+        let counter_width = COUNTER_DIGITS*COUNTER_WIDTH;
+        let counter_y = BTN_Y + ((BTN_SIZE - COUNTER_HEIGHT)/2) as i32;
+
+        Self {
+            window_width: window_width as u32,
+            window_height: window_height as u32,
+            board,
+            counter: rect!(MARGIN, counter_y, counter_width, COUNTER_HEIGHT),
+            button: rect!((window_width - BTN_SIZE)/2, BTN_Y, BTN_SIZE, BTN_SIZE),
+            timer: rect!(
+                window_width - MARGIN - counter_width, 
+                counter_y, 
+                counter_width, 
+                COUNTER_HEIGHT
+            ),
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    pub fn layout() {
+        let layout = Layout::new(30, 16);
+        assert_eq!(layout.window_width, 30*SQ_U32);
+        assert_eq!(layout.window_height, BOARD_Y as u32 + 16*SQ_U32);
+        assert_eq!(layout.button.center().x(), (layout.window_width / 2) as i32);
+        assert_eq!(layout.timer.right(), layout.window_width as i32 - MARGIN as i32);
+
+        // the counters never overlap the button on narrow boards
+        let layout = Layout::new(2, 2);
+        assert_eq!(layout.window_width, Layout::MIN_WIDTH as u32);
+        assert!(layout.counter.right() <= layout.button.left());
+        assert!(layout.button.right() <= layout.timer.left());
+
+        // a column to spare for the squares that wrap around
+        let mut board = Board::new(30, 16);
+        board.set_wrap(Wrap { horizontal: true, vertical: false });
+        let layout = Layout::for_board(&board);
+        assert_eq!(layout.window_width, 31*SQ_U32);
+        assert_eq!(layout.window_height, BOARD_Y as u32 + 16*SQ_U32);
+        assert_eq!(layout.board.width(), 30*SQ_U32);
+
+        // half a hexagon more for the shifted rows, which overlap
+        board.set_topology("grid hex".parse().unwrap());
+        let layout = Layout::for_board(&board);
+        assert_eq!(layout.window_width, 30*SQ_U32 + SQ_U32/2);
+        assert_eq!(layout.window_height, BOARD_Y as u32 + 15*12 + SQ_U32);
+    }
+}
@@ -1,14 +1,59 @@
+//! Minesweeper. The game logic builds on its own; the SDL2 frontend is
+//! behind the `sdl` feature, which is on by default.
+
 pub mod board;
+pub mod difficulty;
+pub mod game;
+pub mod metrics;
+pub mod options;
+pub mod replay;
+pub mod save;
+pub mod scores;
+pub mod solver;
+pub mod timer;
+pub mod action;
+
+#[cfg(feature = "sdl")]
 pub mod button;
+#[cfg(feature = "sdl")]
+pub mod dialog;
+#[cfg(feature = "sdl")]
 pub mod draw;
+#[cfg(feature = "sdl")]
+pub mod font;
+#[cfg(feature = "sdl")]
+pub mod hex;
+#[cfg(feature = "sdl")]
+pub mod layout;
+#[cfg(feature = "sdl")]
 pub mod render;
-pub mod action;
 
 pub use action::*;
 pub use board::*;
+pub use difficulty::*;
+pub use game::*;
+pub use options::*;
+pub use replay::*;
+pub use save::*;
+pub use scores::*;
+pub use timer::*;
+
+#[cfg(feature = "sdl")]
+pub use button::*;
+#[cfg(feature = "sdl")]
+pub use dialog::*;
+#[cfg(feature = "sdl")]
 pub use draw::*;
+#[cfg(feature = "sdl")]
+pub use font::*;
+#[cfg(feature = "sdl")]
+pub use hex::*;
+#[cfg(feature = "sdl")]
+pub use layout::*;
+#[cfg(feature = "sdl")]
 pub use render::*;
 
+#[cfg(feature = "sdl")]
 #[macro_export]
 macro_rules! rect {
     ($x: expr, $y: expr, $w: expr, $h: expr) => {
@@ -16,12 +61,16 @@ macro_rules! rect {
     };
 }
 
+/// Something on screen that can be clicked
+#[cfg(feature = "sdl")]
 pub trait ClickRect {
-    const POS_X: i32;
-    const POS_Y: i32;
-    const WIDTH: usize;
-    const HEIGHT: usize;
-    const Z: i32;
+    /// The area that takes clicks, in pixels
+    fn click_rect(&self) -> sdl2::rect::Rect;
+
+    /// Whether the point `(x, y)` in pixels is over this.
+    fn contains(&self, x: i32, y: i32) -> bool {
+        self.click_rect().contains_point((x, y))
+    }
 }
 
 #[derive(Debug)]
@@ -1,36 +1,148 @@
-use std::time::Duration;
+use std::fs;
+use std::path::Path;
+use std::time::{ Duration, Instant };
 use sdl2::rect::Rect;
 use sdl2::event::EventType;
 use sdl2::event::Event;
-use sdl2::keyboard::Keycode;
+use sdl2::keyboard::{ Keycode, Mod };
 use sdl2::mouse::MouseButton;
 
 use rustsweeper::*;
 
+/// Everything between an input and the game, for inputs from the player
+/// and from replays alike
+struct Input {
+    state: InputState,
+    face: FaceButton,
+    key_cursor: KeyCursor,
+    /// Only shown once the keyboard is used, until the mouse moves again
+    show_key_cursor: bool,
+    /// Where the mouse is, in pixels
+    mouse: (i32, i32),
+}
+impl Input {
+    fn new(layout: &Layout) -> Self {
+        Self {
+            state: InputState::None,
+            face: FaceButton::new(layout.button),
+            key_cursor: KeyCursor::default(),
+            show_key_cursor: false,
+            mouse: (0, 0),
+        }
+    }
+
+    /// Works out what `input` does to `game`.
+    fn route(&mut self, game: &Game, input: ReplayInput) -> Action {
+        match input {
+            ReplayInput::Motion(x, y) => {
+                self.mouse = (x, y);
+                self.show_key_cursor = false;
+                Action::None
+            },
+            // a held mouse button has the board until it is let go
+            ReplayInput::Key(_) if !matches!(self.state, InputState::None) => Action::None,
+            ReplayInput::Key(key_action) => {
+                self.show_key_cursor = true;
+                self.key_cursor.transition(key_action, game.board())
+            },
+            ReplayInput::Click(input_action, x, y) => {
+                self.mouse = (x, y);
+                let square = game.board().square_at(x, y);
+
+                match input_action {
+                    InputAction::LeftDown if self.face.press(x, y) => Action::None,
+                    InputAction::LeftUp if self.face.is_held() => self.face.release(x, y),
+                    InputAction::LeftDown | InputAction::RightDown if square.is_none()
+                        => Action::None,
+                    // releases always go through so a press dragged off
+                    // the board doesn't leave the state stuck
+                    _ => {
+                        let p = square.unwrap_or_default();
+                        let action = self.state.transition(input_action, p);
+                        if square.is_some() { action } else { Action::None }
+                    },
+                }
+            },
+        }
+    }
+
+    /// Draws the counters, the face and the board.
+    fn render(
+        &self,
+        canvas: &mut Canvas<Window>,
+        spritesheet: &Spritesheet,
+        game: &Game,
+        layout: &Layout,
+        seconds: i64,
+    ) -> Result<(), String> {
+        let board = game.board();
+        let (x, y) = self.mouse;
+        let cursor = board.square_at(x, y);
+        let face_sprite = self.face.sprite(game.state(), &self.state, self.mouse);
+        spritesheet.draw(canvas, face_sprite, layout.button)?;
+        render_counter(canvas, spritesheet, game.mines_left(), layout.counter)?;
+        render_counter(canvas, spritesheet, seconds, layout.timer)?;
+
+        board.render(canvas, spritesheet, game.state(), &self.state, cursor)?;
+        render_wrap(canvas, spritesheet, board, game.state())?;
+        if self.show_key_cursor {
+            render_key_cursor(canvas, board, &self.key_cursor)?;
+        }
+        Ok(())
+    }
+}
+
+/// A dialog open over the board, which has the keyboard and the mouse
+/// buttons to itself
+enum Dialog {
+    Custom(CustomDialog),
+    /// Naming a new record, which is kept once it is named
+    Name(NameDialog, Score),
+    Scores(ScoresDialog),
+}
+
 fn main() -> Result<(), String> {
+    let options = Options::from_args(std::env::args().skip(1))?;
+    if let Some(path) = &options.replay {
+        return play_back(Playback::new(Replay::load(path)?));
+    }
 
-    let mut game_state = GameState::Init;
-    let mut input_state = InputState::None;
+    let save_path = data_dir().map(|dir| dir.join("save"));
+    let saved = save_path.as_deref().and_then(offer_saved_game);
+    // a replay of a resumed game would be missing its start
+    let mut resumed = saved.is_some();
+    let mut game = match saved {
+        Some(saved) => Game::resume(saved),
+        None => Game::new(options.settings),
+    };
+    let mut recorder = Recorder::new();
 
-    let width = 30;
-    let height = 16;
-    let num_mines = 50;
+    let scores_path = data_dir().map(|dir| dir.join("scores"));
+    let mut scoreboard = match &scores_path {
+        Some(path) => Scoreboard::load(path).unwrap_or_else(|e| {
+            eprintln!("{}", e);
+            move_aside(path);
+            Scoreboard::default()
+        }),
+        None => Scoreboard::default(),
+    };
 
-    let mut board = Board::new(width, height);
-    let board_rect = rect!(
-        BOARD_X, 
-        BOARD_Y, 
-        board.width()*SQ_SIZE,
-        board.height()*SQ_SIZE
-    );
-    let btn_rect = rect!(
-        BTN_X,
-        BTN_Y,
-        BTN_SIZE,
-        BTN_SIZE
-    );
+    let mut show_probabilities = false;
+    // computed at most once per change to the board
+    let mut probabilities = None;
+    let mut hint = None;
+    // the metrics of the game just finished
+    let mut metrics: Option<metrics::Metrics> = None;
+    let mut show_metrics = true;
+
+    let mut dialog: Option<Dialog> = None;
+    // set by a key press, applied once the events are handled
+    let mut switch_to = None;
+
+    let mut layout = Layout::for_board(game.board());
+    let mut input = Input::new(&layout);
 
-    let (mut canvas, mut event_pump) = init_sdl2(width, height)?;
+    let (mut canvas, mut event_pump) = init_sdl2(&layout)?;
     let texture_creator = canvas.texture_creator();
     let spritesheet = Spritesheet::new(&texture_creator)?;
 
@@ -38,95 +150,418 @@ fn main() -> Result<(), String> {
         canvas.clear();
 
         for event in event_pump.poll_iter() {
-            let action = match &event {
+            let replay_input = match &event {
+                Event::KeyDown { keycode: Some(keycode), keymod, .. } if dialog.is_some() => {
+                    let shift = keymod.intersects(Mod::LSHIFTMOD | Mod::RSHIFTMOD);
+                    dialog = match dialog.take() {
+                        Some(Dialog::Custom(mut custom)) => match custom.handle_key(
+                            *keycode, shift
+                        ) {
+                            DialogResult::Open => Some(Dialog::Custom(custom)),
+                            DialogResult::Cancel => None,
+                            DialogResult::Done(difficulty) => {
+                                switch_to = Some(difficulty);
+                                None
+                            },
+                        },
+                        Some(Dialog::Name(mut name, mut score)) => match name.handle_key(*keycode) {
+                            DialogResult::Open => Some(Dialog::Name(name, score)),
+                            DialogResult::Cancel => None,
+                            DialogResult::Done(name) => {
+                                score.name = name;
+                                let scores = keep_score(
+                                    &mut scoreboard, scores_path.as_deref(), score
+                                );
+                                Some(Dialog::Scores(scores))
+                            },
+                        },
+                        Some(Dialog::Scores(mut scores)) => match scores.handle_key(
+                            *keycode, &scoreboard
+                        ) {
+                            DialogResult::Open => Some(Dialog::Scores(scores)),
+                            _ => None,
+                        },
+                        None => None,
+                    };
+                    None
+                },
+                Event::MouseButtonDown { .. } | Event::MouseButtonUp { .. } 
+                    if dialog.is_some() => None,
+
                 Event::Quit { .. }
                 | Event::KeyDown {
                     keycode: Some(Keycode::Escape) | Some(Keycode::Q),
                     ..
-                } => Action::Quit,
+                } => break 'running,
 
-                edge @ Event::MouseButtonUp { mouse_btn, x, y, .. } 
-                | edge @ Event::MouseButtonDown { mouse_btn, x, y, .. } => {
-                    if board_rect.contains_point((*x, *y)) {
-                        let input_action = match (edge, mouse_btn) {
-                            (Event::MouseButtonUp {..}, MouseButton::Left) 
-                                => InputAction::LeftUp,
-                            (Event::MouseButtonDown {..}, MouseButton::Left) 
-                                => InputAction::LeftDown,
-                            (Event::MouseButtonUp {..}, MouseButton::Right) 
-                                => InputAction::RightUp,
-                            (Event::MouseButtonDown {..}, MouseButton::Right) 
-                                => InputAction::RightDown,
-                            _ => InputAction::None,
-                        };
-
-                        let (x, y) = Board::coord(*x, *y);                   
-                        input_state.transition(input_action, (x, y))
-                    } else if btn_rect.contains_point((*x, *y)) {
-                        Action::Btn
-                    } else {
-                        Action::None
-                    }
+                Event::KeyDown { keycode: Some(Keycode::Num1), .. } => {
+                    switch_to = Some(Difficulty::Beginner);
+                    None
                 },
-
-                _ => Action::None 
-            };
-
-            match (&game_state, action) {
-                (_, Action::Quit) => break 'running,
-                (GameState::Init, Action::Open(x, y)) => {
-                    game_state = GameState::Play;
-                    board.generate(num_mines, (x, y));
-                    board.open((x, y));
+                Event::KeyDown { keycode: Some(Keycode::Num2), .. } => {
+                    switch_to = Some(Difficulty::Intermediate);
+                    None
                 },
-                (GameState::Play, Action::Open(x, y)) => {
-                    if board.open((x, y)) {
-                        game_state = GameState::Lose;
-                    }
+                Event::KeyDown { keycode: Some(Keycode::Num3), .. } => {
+                    switch_to = Some(Difficulty::Expert);
+                    None
                 },
-                (GameState::Play, Action::Chord(x, y)) => {
-                    if board.chord((x, y)) {
-                        game_state = GameState::Lose;
+                Event::KeyDown { keycode: Some(Keycode::Num4), .. } => {
+                    dialog = Some(Dialog::Custom(CustomDialog::new(game.difficulty())));
+                    None
+                },
+                Event::KeyDown { keycode: Some(Keycode::T), .. } => {
+                    let scores = ScoresDialog::new(&scoreboard, game.difficulty(), 0);
+                    dialog = Some(Dialog::Scores(scores));
+                    None
+                },
+
+                Event::KeyDown { keycode: Some(Keycode::H), .. } => {
+                    if game.supports_hints() {
+                        hint = game.hint();
+                    } else {
+                        eprintln!("no hints with several mines per square");
                     }
+                    None
+                },
+
+                Event::KeyDown { keycode: Some(Keycode::M), .. } => {
+                    show_metrics = !show_metrics;
+                    None
                 },
-                (GameState::Play, Action::Flag(x, y)) => {
-                    if board[(x, y)].open == false {
-                        board[(x, y)].flag = !board[(x, y)].flag;
+
+                Event::KeyDown { keycode: Some(Keycode::P), .. } => {
+                    if game.supports_hints() {
+                        show_probabilities = !show_probabilities;
+                        probabilities = None;
+                    } else {
+                        eprintln!("no probabilities with several mines per square");
                     }
+                    None
                 },
-                (GameState::Play, Action::Btn) 
-                | (GameState::Lose, Action::Btn) => {
-                    game_state = GameState::Init;
-                    board.reset();
+
+                Event::KeyDown { keycode: Some(keycode), .. }
+                    => key_action(*keycode).map(ReplayInput::Key),
+
+                Event::MouseMotion { x, y, .. } => Some(ReplayInput::Motion(*x, *y)),
+
+                edge @ Event::MouseButtonUp { mouse_btn, x, y, .. } 
+                | edge @ Event::MouseButtonDown { mouse_btn, x, y, .. } => {
+                    let input_action = match (edge, mouse_btn) {
+                        (Event::MouseButtonUp {..}, MouseButton::Left) 
+                            => InputAction::LeftUp,
+                        (Event::MouseButtonDown {..}, MouseButton::Left) 
+                            => InputAction::LeftDown,
+                        (Event::MouseButtonUp {..}, MouseButton::Right) 
+                            => InputAction::RightUp,
+                        (Event::MouseButtonDown {..}, MouseButton::Right) 
+                            => InputAction::RightDown,
+                        _ => InputAction::None,
+                    };
+                    Some(ReplayInput::Click(input_action, *x, *y))
                 },
-                (_, _) => {}
+
+                _ => None
+            };
+            let Some(replay_input) = replay_input else { continue };
+
+            recorder.record(replay_input);
+            let action = input.route(&game, replay_input);
+            let events = game.apply(action);
+            if !events.is_empty() {
+                probabilities = None;
+                hint = None;
+            }
+            for event in events {
+                match event {
+                    GameEvent::NoGuessFailed(e) => {
+                        eprintln!("no-guess generation failed: {}", e);
+                    },
+                    GameEvent::Won | GameEvent::Lost { .. } => {
+                        metrics = Some(metrics::Metrics::of(&game));
+                        if let GameEvent::Won = event {
+                            let name = scoreboard.last_name().unwrap_or_default().to_string();
+                            if let Some(score) = Score::of(&game, &name) {
+                                if let Some(place) = scoreboard.place(&score) {
+                                    let name = NameDialog::new(&name, place);
+                                    dialog = Some(Dialog::Name(name, score));
+                                }
+                            }
+                        }
+                        let replay = recorder.finish(&game);
+                        if let (Some(dir), false) = (&options.record, resumed) {
+                            match replay.save_in(dir) {
+                                Ok(path) => println!("replay saved to {}", path.display()),
+                                Err(e) => eprintln!("can't save the replay: {}", e),
+                            }
+                        }
+                    },
+                    GameEvent::Reset => {
+                        recorder.restart();
+                        resumed = false;
+                        metrics = None;
+                    },
+                    _ => {},
+                }
             }
         }
 
-        let mouse_state = event_pump.mouse_state();
-        let (x, y) = Board::coord(mouse_state.x(), mouse_state.y());
-        spritesheet.draw(&mut canvas, Sprite::BtnSmile, rect!(BTN_X, BTN_Y, BTN_SIZE, BTN_SIZE))?;
+        if let Some(next) = switch_to.take() {
+            game.set_difficulty(next);
+            layout = Layout::for_board(game.board());
+            canvas.window_mut()
+                .set_size(layout.window_width, layout.window_height)
+                .map_err(|e| e.to_string())?;
+            input = Input { mouse: input.mouse, ..Input::new(&layout) };
+            recorder.restart();
+            resumed = false;
+            hint = None;
+            probabilities = None;
+            metrics = None;
+        }
 
-        board.render(&mut canvas, &spritesheet, &game_state, &input_state)?;
+        let seconds = game.timer().elapsed().as_secs() as i64;
+        input.render(&mut canvas, &spritesheet, &game, &layout, seconds)?;
+        if show_probabilities {
+            if let GameState::Play = game.state() {
+                let board = game.board();
+                let probabilities = probabilities
+                    .get_or_insert_with(|| solver::probabilities(board));
+                if let Some(probabilities) = probabilities {
+                    render_probabilities(&mut canvas, board, probabilities)?;
+                }
+            }
+        }
+        if let Some(hint) = &hint {
+            render_hint(&mut canvas, game.board(), hint)?;
+        }
+        if let (Some(metrics), true, None) = (&metrics, show_metrics, &dialog) {
+            render_metrics(&mut canvas, metrics, game.board().click_rect())?;
+        }
+        if let Some(dialog) = &dialog {
+            let area = rect!(0, 0, layout.window_width, layout.window_height);
+            match dialog {
+                Dialog::Custom(custom) => custom.render(&mut canvas, &spritesheet, area)?,
+                Dialog::Name(name, _) => name.render(&mut canvas, area)?,
+                Dialog::Scores(scores) => scores.render(&mut canvas, &scoreboard, area)?,
+            }
+        }
         canvas.present();
         std::thread::sleep(Duration::new(0, 1_000_000_000u32 / 60));
     }
 
+    if let (Some(path), Some(saved)) = (&save_path, SavedGame::of(&game)) {
+        if let Err(e) = saved.save(path) {
+            eprintln!("can't save the game to {}: {}", path.display(), e);
+        }
+    }
+
     Ok(())
 }
 
+/// Adds a named record to the scores and saves them, returning the scores
+/// to show it in.
+fn keep_score(scoreboard: &mut Scoreboard, path: Option<&Path>, score: Score) -> ScoresDialog {
+    let difficulty = score.difficulty;
+    let place = scoreboard.insert(score).unwrap_or(0);
+    if let Some(path) = path {
+        if let Err(e) = scoreboard.save(path) {
+            eprintln!("can't save the scores to {}: {}", path.display(), e);
+        }
+    }
+    ScoresDialog::new(scoreboard, difficulty, place)
+}
+
+/// Moves a file that couldn't be read out of the way, so that it isn't
+/// written over and can still be looked at.
+fn move_aside(path: &Path) {
+    let aside = path.with_extension("bad");
+    match fs::rename(path, &aside) {
+        Ok(()) => eprintln!("moved it to {}", aside.display()),
+        Err(e) => eprintln!("can't move {} aside: {}", path.display(), e),
+    }
+}
+
+/// Reads the game saved when the window was last closed and asks whether
+/// to carry on with it. A save that can't be read is moved aside.
+fn offer_saved_game(path: &Path) -> Option<SavedGame> {
+    use sdl2::messagebox::*;
+
+    if !path.exists() {
+        return None;
+    }
+    let saved = match SavedGame::load(path) {
+        Ok(saved) => saved,
+        Err(e) => {
+            eprintln!("{}", e);
+            move_aside(path);
+            return None;
+        },
+    };
+    // it's only offered once, and saved again on quitting if it's resumed
+    if let Err(e) = fs::remove_file(path) {
+        eprintln!("can't remove {}: {}", path.display(), e);
+    }
+
+    let buttons = [
+        ButtonData {
+            flags: MessageBoxButtonFlag::RETURNKEY_DEFAULT,
+            button_id: 0,
+            text: "Resume",
+        },
+        ButtonData {
+            flags: MessageBoxButtonFlag::ESCAPEKEY_DEFAULT,
+            button_id: 1,
+            text: "New game",
+        },
+    ];
+    let message = format!(
+        "There is a {} game in progress from last time, at {} seconds.",
+        saved.settings.difficulty, saved.elapsed.as_secs(),
+    );
+    let flags = MessageBoxFlag::INFORMATION;
+    match show_message_box(flags, &buttons, "rustsweeper", &message, None, None) {
+        Ok(ClickedButton::CustomButton(ButtonData { button_id: 0, .. })) => Some(saved),
+        Ok(_) => None,
+        Err(e) => {
+            // rather than lose the game
+            eprintln!("can't ask about the saved game, resuming it: {}", e);
+            Some(saved)
+        },
+    }
+}
+
+/// Plays a replay back through the same input handling as a live game.
+///
+/// Space pauses, `.` steps to the next input, 1 to 4 pick a speed from
+/// [`PLAYBACK_SPEEDS`], the left and right arrows seek five seconds and
+/// home goes back to the start.
+fn play_back(mut playback: Playback) -> Result<(), String> {
+    const SEEK: Duration = Duration::from_secs(5);
+
+    let mut game = playback.game();
+    let layout = Layout::for_board(game.board());
+    let mut input = Input::new(&layout);
+    // when the first click was made and how long the game has gone for,
+    // by the replay's clock rather than the real one
+    let mut started = None;
+    let mut clock = Duration::ZERO;
+    let mut diverged = false;
+
+    let (mut canvas, mut event_pump) = init_sdl2(&layout)?;
+    let texture_creator = canvas.texture_creator();
+    let spritesheet = Spritesheet::new(&texture_creator)?;
+    let mut title = String::new();
+    let mut last_frame = Instant::now();
+
+    loop {
+        canvas.clear();
+
+        let mut seek_to = None;
+        let mut step = false;
+        for event in event_pump.poll_iter() {
+            let keycode = match event {
+                Event::Quit { .. } => return Ok(()),
+                Event::KeyDown { keycode: Some(keycode), .. } => keycode,
+                _ => continue,
+            };
+            match keycode {
+                Keycode::Escape | Keycode::Q => return Ok(()),
+                Keycode::Space => playback.paused = !playback.paused,
+                Keycode::Period => {
+                    playback.paused = true;
+                    step = true;
+                },
+                Keycode::Num1 => playback.speed = PLAYBACK_SPEEDS[0],
+                Keycode::Num2 => playback.speed = PLAYBACK_SPEEDS[1],
+                Keycode::Num3 => playback.speed = PLAYBACK_SPEEDS[2],
+                Keycode::Num4 => playback.speed = PLAYBACK_SPEEDS[3],
+                Keycode::Left => seek_to = Some(playback.position().saturating_sub(SEEK)),
+                Keycode::Right => seek_to = Some(playback.position() + SEEK),
+                Keycode::Home => seek_to = Some(Duration::ZERO),
+                _ => {},
+            }
+        }
+
+        let elapsed = last_frame.elapsed();
+        last_frame = Instant::now();
+        let events = if let Some(position) = seek_to {
+            game = playback.game();
+            input = Input::new(&layout);
+            started = None;
+            clock = Duration::ZERO;
+            playback.seek(position).to_vec()
+        } else if step {
+            playback.step().into_iter().collect()
+        } else {
+            playback.advance(elapsed).to_vec()
+        };
+
+        for event in events {
+            let action = input.route(&game, event.input);
+            for game_event in game.apply(action) {
+                match game_event {
+                    GameEvent::Started { .. } => started = Some(event.at),
+                    GameEvent::Reset => started = None,
+                    _ => {},
+                }
+            }
+        }
+        if let (Some(started), GameState::Play) = (started, game.state()) {
+            clock = playback.position().saturating_sub(started);
+        }
+
+        if !diverged {
+            if let Some(divergence) = playback.divergence(&game) {
+                eprintln!("the replay diverged: {}", divergence);
+                diverged = true;
+            }
+        }
+        let status = format!(
+            "rustsweeper replay {}x{}{}{}",
+            playback.speed,
+            if playback.paused { " paused" } else { "" },
+            if playback.is_finished() { " finished" } else { "" },
+            if diverged { " DIVERGED" } else { "" },
+        );
+        if status != title {
+            canvas.window_mut().set_title(&status).map_err(|e| e.to_string())?;
+            title = status;
+        }
+
+        input.render(&mut canvas, &spritesheet, &game, &layout, clock.as_secs() as i64)?;
+        render_pointer(&mut canvas, input.mouse)?;
+        canvas.present();
+        std::thread::sleep(Duration::new(0, 1_000_000_000u32 / 60));
+    }
+}
+
+/// What a key does on the board, if anything.
+fn key_action(keycode: Keycode) -> Option<KeyAction> {
+    let key_action = match keycode {
+        Keycode::Left | Keycode::A => KeyAction::Move(-1, 0),
+        Keycode::Down | Keycode::S | Keycode::J => KeyAction::Move(0, 1),
+        Keycode::Up | Keycode::W | Keycode::K => KeyAction::Move(0, -1),
+        Keycode::Right | Keycode::D | Keycode::L => KeyAction::Move(1, 0),
+        Keycode::Space | Keycode::Return | Keycode::KpEnter => KeyAction::Open,
+        Keycode::F => KeyAction::Flag,
+        Keycode::C => KeyAction::Chord,
+        Keycode::N | Keycode::F2 => KeyAction::Restart,
+        _ => return None,
+    };
+    Some(key_action)
+}
+
 use sdl2::EventPump;
 use sdl2::render::Canvas;
 use sdl2::video::Window;
 
-fn init_sdl2(width: usize, height: usize) -> Result<(Canvas<Window>, EventPump), String> {
-    let (width, height) = (width as u32, height as u32);
-
+fn init_sdl2(layout: &Layout) -> Result<(Canvas<Window>, EventPump), String> {
     let sdl_context = sdl2::init()?;
     let video_subsystem = sdl_context.video()?;
 
     let window = video_subsystem
-        .window("rustsweeper", 32+width*SQ_U32, 32+height*SQ_U32)
+        .window("rustsweeper", layout.window_width, layout.window_height)
         .resizable()
         .position_centered()
         .opengl()
@@ -140,7 +575,6 @@ fn init_sdl2(width: usize, height: usize) -> Result<(Canvas<Window>, EventPump),
 
     let mut event_pump = sdl_context.event_pump()?;
     let disabled_events = [
-        EventType::MouseMotion,
         EventType::Window,
         EventType::TextEditing,
     ];
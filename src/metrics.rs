@@ -0,0 +1,359 @@
+//! Measures of how much work a [`Board`] is to clear, and of how well a
+//! game on it was played.
+//!
+//! All of them read where the mines are, so they only mean anything once
+//! the board has been generated.
+//!
+//! - An opening is a connected patch of squares with no mines around them,
+//!   which a single click clears along with the numbers on its edge.
+//! - An island is a connected patch of numbered squares that no opening
+//!   reaches, each of which has to be opened by itself.
+//! - 3BV is the fewest clicks that clear the board without flagging: one
+//!   for every opening and one for every square on an island.
+//! - ZiNi estimates the fewest clicks when flagging and chording are used
+//!   as well, by greedily chording wherever that saves the most clicks.
+
+use std::fmt;
+use std::time::Duration;
+
+use crate::{ Board, Game, GameState };
+
+/// Where a safe square's click counts towards 3BV
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Unit {
+    /// An opening, which is cleared by clicking any of its blank squares
+    Opening(usize),
+    /// A square on an island, which is cleared by clicking it
+    Island(usize),
+    /// A number on the edge of an opening, cleared along with it
+    Edge,
+    Mine,
+}
+
+/// The unit of every square of a board, by `y*width + x`
+struct Units {
+    units: Vec<Unit>,
+    openings: usize,
+    island_squares: usize,
+}
+impl Units {
+    fn of(board: &Board) -> Self {
+        let width = board.width();
+        let index = |(x, y): (usize, usize)| y*width + x;
+        let mut units: Vec<Option<Unit>> = vec![None; width*board.height()];
+        let mut openings = 0;
+
+        for p in squares(board) {
+            if board[p].is_mine() {
+                units[index(p)] = Some(Unit::Mine);
+            }
+            if board[p].is_mine() || board[p].num != 0 || units[index(p)].is_some() {
+                continue;
+            }
+            let opening = Unit::Opening(openings);
+            openings += 1;
+            units[index(p)] = Some(opening);
+            let mut stack = vec![p];
+            while let Some(p) = stack.pop() {
+                for q in board.get_neighborhood(p) {
+                    // a number between two openings is on the first one's edge
+                    if units[index(q)].is_some() {
+                        continue;
+                    }
+                    if board[q].num == 0 {
+                        units[index(q)] = Some(opening);
+                        stack.push(q);
+                    } else {
+                        units[index(q)] = Some(Unit::Edge);
+                    }
+                }
+            }
+        }
+
+        let mut island_squares = 0;
+        let units = units.into_iter()
+            .map(|unit| unit.unwrap_or_else(|| {
+                island_squares += 1;
+                Unit::Island(island_squares - 1)
+            }))
+            .collect();
+        Self { units, openings, island_squares }
+    }
+
+    fn bbbv(&self) -> usize {
+        self.openings + self.island_squares
+    }
+}
+
+fn squares(board: &Board) -> impl Iterator<Item=(usize, usize)> {
+    let (width, height) = (board.width(), board.height());
+    (0..height).flat_map(move |y| (0..width).map(move |x| (x, y)))
+}
+
+/// The 3BV of `board`: the fewest clicks that clear it without flagging.
+pub fn bbbv(board: &Board) -> usize {
+    Units::of(board).bbbv()
+}
+
+/// How many openings there are on `board`.
+pub fn openings(board: &Board) -> usize {
+    Units::of(board).openings
+}
+
+/// How many islands there are on `board`.
+pub fn islands(board: &Board) -> usize {
+    islands_in(board, &Units::of(board))
+}
+
+fn islands_in(board: &Board, units: &Units) -> usize {
+    let width = board.width();
+    let units = &units.units;
+    let is_island = |p: (usize, usize)| matches!(units[p.1*width + p.0], Unit::Island(_));
+    let mut seen = vec![false; units.len()];
+    let mut islands = 0;
+
+    for p in squares(board) {
+        if !is_island(p) || seen[p.1*width + p.0] {
+            continue;
+        }
+        islands += 1;
+        seen[p.1*width + p.0] = true;
+        let mut stack = vec![p];
+        while let Some(p) = stack.pop() {
+            for q in board.get_neighborhood(p) {
+                if is_island(q) && !seen[q.1*width + q.0] {
+                    seen[q.1*width + q.0] = true;
+                    stack.push(q);
+                }
+            }
+        }
+    }
+    islands
+}
+
+/// An estimate of the fewest clicks that clear `board` with flagging and
+/// chording, which is never more than its 3BV.
+///
+/// Over and over, this picks the number whose chord clears the most 3BV
+/// for the clicks it costs: opening the number if it isn't open, flagging
+/// the mines around it and the chord itself. Once no chord saves anything
+/// the rest is cleared a click at a time.
+pub fn zini(board: &Board) -> usize {
+    zini_in(board, &Units::of(board))
+}
+
+fn zini_in(board: &Board, units: &Units) -> usize {
+    let width = board.width();
+    let index = |(x, y): (usize, usize)| y*width + x;
+    let Units { units, openings, island_squares } = units;
+    let mut open = vec![false; units.len()];
+    let mut flagged = vec![false; units.len()];
+    let mut opening_done = vec![false; *openings];
+    let mut island_done = vec![false; *island_squares];
+    let mut clicks = 0;
+
+    // the units that opening `p` clears
+    let cleared_by = |
+        p: (usize, usize),
+        open: &[bool],
+        opening_done: &[bool],
+        island_done: &[bool],
+    | match units[index(p)] {
+        _ if open[index(p)] => None,
+        Unit::Opening(i) if !opening_done[i] => Some(Unit::Opening(i)),
+        Unit::Island(i) if !island_done[i] => Some(Unit::Island(i)),
+        _ => None,
+    };
+
+    loop {
+        let mut best: Option<((usize, usize), isize)> = None;
+        for p in squares(board) {
+            if board[p].is_mine() || board[p].num == 0 {
+                continue;
+            }
+            let mut gained = vec![];
+            for q in board.get_neighborhood(p).chain([p]) {
+                if let Some(unit) = cleared_by(q, &open, &opening_done, &island_done) {
+                    if !gained.contains(&unit) {
+                        gained.push(unit);
+                    }
+                }
+            }
+            if gained.is_empty() {
+                continue;
+            }
+            // a click for each flag, however many a square needs
+            let flags: usize = board.get_neighborhood(p)
+                .filter(|&q| !flagged[index(q)])
+                .map(|q| board[q].mines as usize)
+                .sum();
+            let cost = !open[index(p)] as usize + flags + 1;
+            let premium = gained.len() as isize - cost as isize;
+            if premium > 0 && best.is_none_or(|(_, most)| premium > most) {
+                best = Some((p, premium));
+            }
+        }
+        let Some((p, _)) = best else { break };
+
+        clicks += !open[index(p)] as usize + 1;
+        for q in board.get_neighborhood(p).chain([p]) {
+            if board[q].is_mine() {
+                if !flagged[index(q)] {
+                    clicks += board[q].mines as usize;
+                }
+                flagged[index(q)] = true;
+                continue;
+            }
+            match cleared_by(q, &open, &opening_done, &island_done) {
+                Some(Unit::Opening(i)) => opening_done[i] = true,
+                Some(Unit::Island(i)) => island_done[i] = true,
+                _ => {},
+            }
+            open[index(q)] = true;
+        }
+        // whatever an opening cleared is open now too, edges and all
+        for p in squares(board) {
+            if let Unit::Opening(opening) = units[index(p)] {
+                if opening_done[opening] {
+                    open[index(p)] = true;
+                    for q in board.get_neighborhood(p) {
+                        open[index(q)] = true;
+                    }
+                }
+            }
+        }
+    }
+
+    clicks
+        + opening_done.iter().filter(|done| !**done).count()
+        + island_done.iter().filter(|done| !**done).count()
+}
+
+/// 3BV as a percentage of the clicks made.
+pub fn efficiency(bbbv: usize, clicks: usize) -> usize {
+    if clicks == 0 {
+        return 0;
+    }
+    bbbv * 100 / clicks
+}
+
+/// The measures of a finished game's board, and of how it was played
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Metrics {
+    pub bbbv: usize,
+    pub openings: usize,
+    pub islands: usize,
+    pub zini: usize,
+    /// All the clicks made on the board
+    pub clicks: usize,
+    pub time: Duration,
+    pub won: bool,
+}
+impl Metrics {
+    pub fn of(game: &Game) -> Self {
+        let won = matches!(game.state(), GameState::Win);
+        Self::new(game.board(), game.clicks().total(), game.timer().elapsed(), won)
+    }
+
+    /// The measures of `board`, played with `clicks` clicks over `time`.
+    pub fn new(board: &Board, clicks: usize, time: Duration, won: bool) -> Self {
+        let units = Units::of(board);
+        Self {
+            bbbv: units.bbbv(),
+            openings: units.openings,
+            islands: islands_in(board, &units),
+            zini: zini_in(board, &units),
+            clicks,
+            time,
+            won,
+        }
+    }
+
+    /// 3BV per second, which only means much for a won game.
+    pub fn bbbv_per_second(&self) -> f64 {
+        if self.time.is_zero() {
+            return 0.0;
+        }
+        self.bbbv as f64 / self.time.as_secs_f64()
+    }
+
+    pub fn efficiency(&self) -> usize {
+        efficiency(self.bbbv, self.clicks)
+    }
+}
+
+impl fmt::Display for Metrics {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f, "3BV {}, ZiNi {}, {} openings, {} islands",
+            self.bbbv, self.zini, self.openings, self.islands
+        )?;
+        if self.won {
+            write!(
+                f, ", {:.2} 3BV/s, {}% efficiency",
+                self.bbbv_per_second(), self.efficiency()
+            )?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::MineBoard;
+
+    #[test]
+    pub fn bbbv() {
+        // one opening clears everything
+        assert_eq!(super::bbbv(&Board::with_mines(3, 3, &[(0, 0)])), 1);
+        // two numbers either side of a mine
+        assert_eq!(super::bbbv(&Board::with_mines(3, 1, &[(1, 0)])), 2);
+        // an opening down the left, and five numbers it doesn't reach
+        let board = Board::with_mines(5, 3, &[(2, 0), (2, 1), (3, 2), (4, 1)]);
+        assert_eq!(board[(1, 1)].num, 2);
+        assert_eq!(super::bbbv(&board), 6);
+    }
+
+    #[test]
+    pub fn openings_and_islands() {
+        let board = Board::with_mines(5, 3, &[(2, 0), (2, 1), (3, 2), (4, 1)]);
+        assert_eq!(openings(&board), 1);
+        // the five numbers on the right all touch (3, 1)
+        assert_eq!(islands(&board), 1);
+
+        // a wall of mines between two openings
+        let wall: Vec<_> = (0..3).map(|y| (3, y)).collect();
+        let board = Board::with_mines(7, 3, &wall);
+        assert_eq!((openings(&board), islands(&board)), (2, 0));
+        assert_eq!(super::bbbv(&board), 2);
+    }
+
+    #[test]
+    pub fn zini() {
+        // nothing to gain from chording an opening
+        assert_eq!(super::zini(&Board::with_mines(3, 3, &[(0, 0)])), 1);
+        // eight numbers around a mine: open the top middle, flag the mine
+        // and chord, chord the left middle, then open the last corner
+        let board = Board::with_mines(3, 3, &[(1, 1)]);
+        assert_eq!(super::bbbv(&board), 8);
+        assert_eq!(super::zini(&board), 5);
+
+        // never more than 3BV
+        let mut board = Board::new(30, 16);
+        board.generate_seeded(99, (0, 0), 7);
+        assert!(super::zini(&board) <= super::bbbv(&board));
+    }
+
+    #[test]
+    pub fn metrics() {
+        // won by opening both ends around the mine in the middle
+        let mut board = Board::with_mines(3, 1, &[(1, 0)]);
+        board.open((0, 0));
+        board.open((2, 0));
+        let metrics = Metrics::new(&board, 2, Duration::from_secs(1), true);
+        assert!(metrics.won);
+        assert_eq!((metrics.bbbv, metrics.clicks, metrics.efficiency()), (2, 2, 100));
+        assert!(metrics.to_string().starts_with("3BV 2, ZiNi 2, 0 openings, 2 islands, "));
+    }
+}
@@ -0,0 +1,157 @@
+use std::path::PathBuf;
+
+use crate::*;
+
+/// Everything that can be set from the command line
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Options {
+    pub settings: Settings,
+    /// Write a replay of every finished game into this directory
+    pub record: Option<PathBuf>,
+    /// Play back this replay instead of playing
+    pub replay: Option<PathBuf>,
+}
+impl Options {
+    /// Reads command line options, without the program name.
+    pub fn from_args(mut args: impl Iterator<Item=String>) -> Result<Self, String> {
+        let mut options = Options::default();
+        let settings = &mut options.settings;
+        while let Some(arg) = args.next() {
+            match arg.as_str() {
+                "--seed" => {
+                    let seed = args.next()
+                        .ok_or("--seed needs a value")?;
+                    let seed = seed.parse()
+                        .map_err(|e| format!("invalid seed {:?}: {}", seed, e))?;
+                    settings.seed = Some(seed);
+                },
+                "--no-guess" => settings.no_guess = true,
+                "--beginner" => settings.difficulty = Difficulty::Beginner,
+                "--intermediate" => settings.difficulty = Difficulty::Intermediate,
+                "--expert" => settings.difficulty = Difficulty::Expert,
+                "--custom" => {
+                    let custom = args.next()
+                        .ok_or("--custom needs a value like 20x10x30")?;
+                    settings.difficulty = custom.parse()
+                        .map_err(|e: DifficultyError| e.to_string())?;
+                },
+                "--wrap" => {
+                    let wrap = args.next()
+                        .ok_or("--wrap needs none, horizontal, vertical or both")?;
+                    settings.topology.wrap = wrap.parse()
+                        .map_err(|e: ParseWrapError| e.to_string())?;
+                },
+                "--grid" => {
+                    let grid = args.next()
+                        .ok_or("--grid needs square or hex")?;
+                    settings.topology.grid = grid.parse()
+                        .map_err(|e: ParseGridError| e.to_string())?;
+                },
+                "--neighborhood" => {
+                    let neighborhood = args.next()
+                        .ok_or("--neighborhood needs king, cross, knight, radius2 or offsets")?;
+                    settings.topology.neighborhood = neighborhood.parse()
+                        .map_err(|e: ParseNeighborhoodError| e.to_string())?;
+                },
+                "--mines-per-square" => {
+                    let most = args.next()
+                        .ok_or("--mines-per-square needs a value")?;
+                    settings.mines_per_square = most.parse()
+                        .ok()
+                        .filter(|most| (1..=Cell::MAX_MINES).contains(most))
+                        .ok_or_else(|| format!(
+                            "invalid mines per square {:?}, it must be from 1 to {}",
+                            most, Cell::MAX_MINES,
+                        ))?;
+                },
+                "--record" => {
+                    let dir = args.next()
+                        .ok_or("--record needs a directory")?;
+                    options.record = Some(dir.into());
+                },
+                "--replay" => {
+                    let file = args.next()
+                        .ok_or("--replay needs a file")?;
+                    options.replay = Some(file.into());
+                },
+                _ => return Err(format!("unknown argument {:?}", arg)),
+            }
+        }
+        if settings.no_guess && settings.mines_per_square > 1 {
+            return Err("--no-guess only works with one mine per square".to_string());
+        }
+        let most = settings.topology.max_mines_per_square();
+        if settings.mines_per_square > most {
+            return Err(format!(
+                "{} mines per square is too many with {} neighbors, \
+                numbers only go up to {} so there can be at most {}",
+                settings.mines_per_square, settings.topology.neighbors(), Cell::MAX_NUM, most,
+            ));
+        }
+        Ok(options)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn args(s: &str) -> Result<Options, String> {
+        Options::from_args(s.split_whitespace().map(String::from))
+    }
+
+    #[test]
+    pub fn from_args() {
+        assert_eq!(args(""), Ok(Options::default()));
+        assert_eq!(
+            args("--seed 7 --no-guess --custom 20x10x30 --wrap horizontal --grid hex"),
+            Ok(Options {
+                settings: Settings {
+                    difficulty: Difficulty::Custom { width: 20, height: 10, mines: 30 },
+                    seed: Some(7),
+                    no_guess: true,
+                    topology: Topology {
+                        grid: Grid::Hex,
+                        wrap: Wrap { horizontal: true, vertical: false },
+                        ..Topology::default()
+                    },
+                    mines_per_square: 1,
+                },
+                record: None,
+                replay: None,
+            }),
+        );
+        assert!(args("--seed").is_err());
+        assert!(args("--record").is_err());
+        assert!(args("--wrap sideways").is_err());
+        assert!(args("--grid triangle").is_err());
+        assert_eq!(
+            args("--neighborhood 0,-2/0,2").map(|options| options.settings.topology.neighborhood),
+            Ok(Neighborhood::from_offsets([(0, -2), (0, 2)]).unwrap()),
+        );
+        assert!(args("--neighborhood 0,4").is_err());
+        assert_eq!(
+            args("--mines-per-square 3").map(|options| options.settings.mines_per_square),
+            Ok(3),
+        );
+        assert!(args("--mines-per-square 0").is_err());
+        assert!(args("--mines-per-square 9").is_err());
+        assert!(args("--mines-per-square 5 --neighborhood radius2").is_err());
+        assert!(args("--no-guess --mines-per-square 2").is_err());
+        assert_eq!(
+            args("--neighborhood radius2 --mines-per-square 4")
+                .map(|options| options.settings.mines_per_square),
+            Ok(4),
+        );
+        assert!(args("--grid hex --neighborhood radius2 --mines-per-square 5").is_ok());
+        assert_eq!(
+            args("--record replays").map(|options| options.record),
+            Ok(Some("replays".into())),
+        );
+        assert_eq!(
+            args("--replay 1.replay").map(|options| options.replay),
+            Ok(Some("1.replay".into())),
+        );
+        assert!(args("--hard").is_err());
+    }
+}
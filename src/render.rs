@@ -1,7 +1,10 @@
 use crate::*;
+use crate::metrics::Metrics;
+use crate::solver::{ Hint, HintKind, Probabilities };
 
+use sdl2::pixels::Color;
 use sdl2::rect::Rect;
-use sdl2::render::Canvas;
+use sdl2::render::{ BlendMode, Canvas };
 use sdl2::video::Window;
 
 pub trait Render {
@@ -11,9 +14,17 @@ pub trait Render {
         spritesheet: &Spritesheet,
         game_state: &GameState,
         input_state: &InputState,
+        cursor: Option<(usize, usize)>,
     ) -> Result<(), String>; 
 }
 
+impl ClickRect for Board {
+    fn click_rect(&self) -> Rect {
+        let (width, height) = self.pixel_size();
+        rect!(BOARD_X, BOARD_Y, width, height)
+    }
+}
+
 impl Render for Board {
     fn render(
         &self, 
@@ -21,75 +32,353 @@ impl Render for Board {
         spritesheet: &Spritesheet,
         game_state: &GameState,
         input_state: &InputState,
+        cursor: Option<(usize, usize)>,
     ) -> Result<(), String> {
+        let pressed = match game_state {
+            GameState::Init | GameState::Play => input_state.pressed(self, cursor),
+            _ => vec![],
+        };
+
         for y in 0..self.height() {
             for x in 0..self.width() {
-                let rect = rect!(
-                    (x*SQ_SIZE) as i32 + BOARD_X,
-                    (y*SQ_SIZE) as i32 + BOARD_Y,
-                    SQ_SIZE, 
-                    SQ_SIZE
-                );
-
-                let tentative = false;
-                match self[(x, y)] {
-                    Cell {
-                        open: false,
-                        flag: true,
-                        mine: false,
-                        ..
-                    } => {
-                        if let GameState::Lose = game_state {
-                            spritesheet.draw(canvas, Sprite::MineCross, rect)?; 
-                        } else {
-                            spritesheet.draw(canvas, Sprite::Flag, rect)?; 
-                        } 
-                    },
-                    Cell {
-                        open: false,
-                        flag: true,
-                        ..
-                    } => {
-                        spritesheet.draw(canvas, Sprite::Flag, rect)?; 
-                    },
-                    Cell {
-                        open: false,
-                        mine: true,
-                        ..
-                    } => {
-                        if let GameState::Lose = game_state {
-                            spritesheet.draw(canvas, Sprite::Mine, rect)?; 
-                        } else if tentative {
-                            spritesheet.draw(canvas, Sprite::Num(0), rect)?; 
-                        } else {
-                            spritesheet.draw(canvas, Sprite::Closed, rect)?; 
-                        }
-                    },
-                    Cell {
-                        open: false,
-                        ..
-                    } => {
-                        if tentative {
-                            spritesheet.draw(canvas, Sprite::Num(0), rect)?; 
-                        } else {
-                            spritesheet.draw(canvas, Sprite::Closed, rect)?; 
-                        }
-                    },
-                    Cell {
-                        mine: true,
-                        ..
-                    } => {
-                        spritesheet.draw(canvas, Sprite::MineRed, rect)?; 
-                    },
-                    Cell {
-                        num: n,
-                        ..
-                    } => {
-                        spritesheet.draw(canvas, Sprite::Num(n), rect)?; 
-                    },
-                }
+                let tentative = pressed.contains(&(x, y));
+                draw_cell(canvas, spritesheet, self, (x, y), self[(x, y)], game_state, tentative)?;
             }
         }
         Ok(())
     }
 }
+
+/// The box the square at `p` is drawn in.
+fn square_rect(board: &Board, p: (usize, usize)) -> Rect {
+    let (x, y) = board.square_origin(p);
+    rect!(x, y, SQ_SIZE, SQ_SIZE)
+}
+
+/// Draws `sprite` for the square at `p`, as a hexagon on a hex board.
+fn draw_square(
+    canvas: &mut Canvas<Window>,
+    spritesheet: &Spritesheet,
+    board: &Board,
+    p: (usize, usize),
+    sprite: Sprite,
+) -> Result<(), String> {
+    let rect = square_rect(board, p);
+    match (board.grid(), sprite) {
+        (Grid::Hex, _) => draw_hex(canvas, sprite, rect),
+        // the spritesheet stops at eight
+        (Grid::Square, Sprite::Num(n)) if n > 8 => {
+            spritesheet.draw(canvas, Sprite::Num(0), rect)?;
+            draw_number(canvas, n, rect)
+        },
+        (Grid::Square, _) => spritesheet.draw(canvas, sprite, rect),
+    }
+}
+
+/// Writes `n` in the middle of `rect` in its colour, for the numbers that
+/// aren't on the spritesheet.
+pub fn draw_number(canvas: &mut Canvas<Window>, n: u8, rect: Rect) -> Result<(), String> {
+    let text = n.to_string();
+    let left = rect.x() + (rect.width() as i32 - text_width(&text, 1) as i32)/2;
+    let top = rect.y() + (rect.height() as i32 - GLYPH_HEIGHT as i32)/2;
+    draw_text(canvas, &text, (left, top), 1, number_color(n))
+}
+
+/// The colour of the number `n` on an open square, as on the spritesheet.
+pub fn number_color(n: u8) -> Color {
+    match n {
+        1 => Color::RGB(0, 0, 255),
+        2 => Color::RGB(0, 128, 0),
+        3 => Color::RGB(255, 0, 0),
+        4 => Color::RGB(0, 0, 128),
+        5 => Color::RGB(128, 0, 0),
+        6 => Color::RGB(0, 128, 128),
+        8 => Color::RGB(128, 128, 128),
+        _ => Color::RGB(0, 0, 0),
+    }
+}
+
+/// Fills the square at `p` with the current draw colour.
+fn fill_square(
+    canvas: &mut Canvas<Window>,
+    board: &Board,
+    p: (usize, usize),
+) -> Result<(), String> {
+    let rect = square_rect(board, p);
+    match board.grid() {
+        Grid::Square => canvas.fill_rect(rect),
+        Grid::Hex => fill_hex(canvas, rect),
+    }
+}
+
+/// How a square looks. `tentative` is for a closed square that a held
+/// mouse button would open.
+fn square_sprite(cell: Cell, game_state: &GameState, tentative: bool) -> Sprite {
+    let lost = matches!(game_state, GameState::Lose);
+    match cell {
+        Cell { open: false, flags, mines, .. } if lost && flags > mines => Sprite::MineCross,
+        Cell { open: false, .. } if cell.is_flagged() => Sprite::Flag,
+        Cell { open: false, .. } if lost && cell.is_mine() => Sprite::Mine,
+        Cell { open: false, .. } if tentative => Sprite::Num(0),
+        Cell { open: false, .. } => Sprite::Closed,
+        _ if cell.is_mine() => Sprite::MineRed,
+        Cell { num, .. } => Sprite::Num(num),
+    }
+}
+
+/// Draws `cell` at `p`, marking a flag or mine with how many there are
+/// when there are several.
+fn draw_cell(
+    canvas: &mut Canvas<Window>,
+    spritesheet: &Spritesheet,
+    board: &Board,
+    p: (usize, usize),
+    cell: Cell,
+    game_state: &GameState,
+    tentative: bool,
+) -> Result<(), String> {
+    let sprite = square_sprite(cell, game_state, tentative);
+    draw_square(canvas, spritesheet, board, p, sprite)?;
+    let count = match sprite {
+        Sprite::Flag => cell.flags,
+        Sprite::Mine | Sprite::MineRed => cell.mines,
+        _ => 1,
+    };
+    if count < 2 {
+        return Ok(());
+    }
+    // a white patch on the right, inside a hexagon as well as a square
+    let rect = square_rect(board, p);
+    let (x, y) = (rect.x(), rect.y());
+    let color = canvas.draw_color();
+    canvas.set_draw_color(Color::RGB(255, 255, 255));
+    canvas.fill_rect(rect!(x + 9, y + 4, 6, 9))?;
+    canvas.set_draw_color(color);
+    draw_text(canvas, &count.to_string(), (x + 10, y + 5), 1, Color::RGB(0, 0, 0))
+}
+
+/// Draws the squares on the far side of the edges that wrap around past
+/// the right and bottom of the board, shaded so they aren't mistaken for
+/// squares that can be clicked.
+pub fn render_wrap(
+    canvas: &mut Canvas<Window>,
+    spritesheet: &Spritesheet,
+    board: &Board,
+    game_state: &GameState,
+) -> Result<(), String> {
+    let wrap = board.wrap();
+    let (width, height) = (board.width(), board.height());
+    let mut ghosts = vec![];
+    if wrap.horizontal {
+        ghosts.extend((0..height).map(|y| ((width, y), (0, y))));
+    }
+    if wrap.vertical {
+        ghosts.extend((0..width).map(|x| ((x, height), (x, 0))));
+    }
+    if wrap.horizontal && wrap.vertical {
+        ghosts.push(((width, height), (0, 0)));
+    }
+
+    let (color, blend_mode) = (canvas.draw_color(), canvas.blend_mode());
+    canvas.set_blend_mode(BlendMode::Blend);
+    for (ghost, p) in ghosts {
+        draw_cell(canvas, spritesheet, board, ghost, board[p], game_state, false)?;
+        canvas.set_draw_color(Color::RGBA(0, 0, 64, 96));
+        fill_square(canvas, board, ghost)?;
+    }
+    canvas.set_draw_color(color);
+    canvas.set_blend_mode(blend_mode);
+    Ok(())
+}
+
+/// Tints every closed, unflagged square from green (certainly safe) to red
+/// (certainly a mine).
+pub fn render_probabilities(
+    canvas: &mut Canvas<Window>,
+    board: &Board,
+    probabilities: &Probabilities,
+) -> Result<(), String> {
+    let (color, blend_mode) = (canvas.draw_color(), canvas.blend_mode());
+    canvas.set_blend_mode(BlendMode::Blend);
+    for y in 0..board.height() {
+        for x in 0..board.width() {
+            if board[(x, y)].open || board[(x, y)].is_flagged() {
+                continue;
+            }
+            let p = probabilities[(x, y)].clamp(0.0, 1.0);
+            canvas.set_draw_color(Color::RGBA(
+                (255.0 * p) as u8,
+                (255.0 * (1.0 - p)) as u8,
+                0,
+                112,
+            ));
+            fill_square(canvas, board, (x, y))?;
+        }
+    }
+    canvas.set_draw_color(color);
+    canvas.set_blend_mode(blend_mode);
+    Ok(())
+}
+
+/// Outlines the square a hint is about, and the numbers that justify it.
+pub fn render_hint(canvas: &mut Canvas<Window>, board: &Board, hint: &Hint) -> Result<(), String> {
+    for &p in &hint.reasons {
+        outline(canvas, board, p, Color::RGB(0, 96, 255))?;
+    }
+    let c = match hint.kind {
+        HintKind::Safe => Color::RGB(0, 200, 0),
+        HintKind::Mine => Color::RGB(255, 0, 0),
+        HintKind::Guess(_) => Color::RGB(255, 200, 0),
+    };
+    outline(canvas, board, hint.square, c)
+}
+
+/// Marks the square picked with the keyboard.
+pub fn render_key_cursor(
+    canvas: &mut Canvas<Window>,
+    board: &Board,
+    cursor: &KeyCursor,
+) -> Result<(), String> {
+    outline(canvas, board, cursor.square, Color::RGB(0, 0, 0))
+}
+
+/// Draws a small cross where a replay's mouse is, since the real
+/// pointer is somewhere else.
+pub fn render_pointer(canvas: &mut Canvas<Window>, (x, y): (i32, i32)) -> Result<(), String> {
+    let color = canvas.draw_color();
+    canvas.set_draw_color(Color::RGB(255, 0, 255));
+    canvas.fill_rects(&[rect!(x - 4, y - 1, 9, 3), rect!(x - 1, y - 4, 3, 9)])?;
+    canvas.set_draw_color(color);
+    Ok(())
+}
+
+/// Draws a two pixel outline just inside the square at `p`.
+fn outline(
+    canvas: &mut Canvas<Window>,
+    board: &Board,
+    p: (usize, usize),
+    c: Color,
+) -> Result<(), String> {
+    if board.grid() == Grid::Hex {
+        return outline_hex(canvas, square_rect(board, p), c);
+    }
+    let color = canvas.draw_color();
+    canvas.set_draw_color(c);
+    let (x, y) = board.square_origin(p);
+    canvas.draw_rect(rect!(x, y, SQ_SIZE, SQ_SIZE))?;
+    canvas.draw_rect(rect!(x + 1, y + 1, SQ_SIZE - 2, SQ_SIZE - 2))?;
+    canvas.set_draw_color(color);
+    Ok(())
+}
+
+/// Draws a grey panel `width` by `height` centred in `area`, for dialogs to
+/// draw on, and returns where it went.
+pub fn render_panel(
+    canvas: &mut Canvas<Window>,
+    area: Rect,
+    width: usize,
+    height: usize,
+) -> Result<Rect, String> {
+    let panel = rect!(
+        area.x() + (area.width() as i32 - width as i32)/2,
+        area.y() + (area.height() as i32 - height as i32)/2,
+        width,
+        height
+    );
+
+    let previous = canvas.draw_color();
+    canvas.set_draw_color(Color::RGB(192, 192, 192));
+    canvas.fill_rect(panel)?;
+    canvas.set_draw_color(Color::RGB(128, 128, 128));
+    canvas.draw_rect(panel)?;
+    canvas.set_draw_color(previous);
+    Ok(panel)
+}
+
+/// Draws the metrics of a finished game on a panel in the middle of
+/// `area`, a measure to a line.
+pub fn render_metrics(
+    canvas: &mut Canvas<Window>,
+    metrics: &Metrics,
+    area: Rect,
+) -> Result<(), String> {
+    const PADDING: usize = 4;
+    const ROW_SPACING: usize = GLYPH_HEIGHT + 3;
+
+    let mut rows = vec![
+        ("3BV", metrics.bbbv.to_string()),
+        ("ZINI", metrics.zini.to_string()),
+        ("OPENINGS", metrics.openings.to_string()),
+        ("ISLANDS", metrics.islands.to_string()),
+    ];
+    // how fast and how well only mean much for a won game
+    if metrics.won {
+        rows.push(("3BV/S", format!("{:.2}", metrics.bbbv_per_second())));
+        rows.push(("EFF", format!("{}%", metrics.efficiency())));
+    }
+    let columns = rows.iter()
+        .map(|(label, value)| label.len() + 1 + value.len())
+        .max()
+        .unwrap_or(0);
+    let width = PADDING + columns*ADVANCE + PADDING;
+    let height = PADDING + rows.len()*ROW_SPACING + PADDING;
+    let panel = render_panel(canvas, area, width, height)?;
+
+    let black = Color::RGB(0, 0, 0);
+    for (row, (label, value)) in rows.iter().enumerate() {
+        let line = format!("{}{:>2$}", label, value, columns - label.len());
+        let y = panel.y() + (PADDING + row*ROW_SPACING) as i32;
+        draw_text(canvas, &line, (panel.x() + PADDING as i32, y), 1, black)?;
+    }
+    Ok(())
+}
+
+/// Draws `value` as a seven-segment counter, showing `-` in front of
+/// negative values. Values that don't fit are clamped.
+pub fn render_counter(
+    canvas: &mut Canvas<Window>,
+    spritesheet: &Spritesheet,
+    value: i64,
+    rect: Rect,
+) -> Result<(), String> {
+    let max = 10i64.pow(COUNTER_DIGITS as u32) - 1;
+    let min = -(10i64.pow(COUNTER_DIGITS as u32 - 1) - 1);
+    let value = value.clamp(min, max);
+
+    let mut digits = vec![];
+    let mut rest = value.abs();
+    for _ in 0..COUNTER_DIGITS {
+        digits.push(Some((rest % 10) as u8));
+        rest /= 10;
+    }
+    if value < 0 {
+        digits[COUNTER_DIGITS - 1] = None;
+    }
+
+    for (i, digit) in digits.iter().rev().enumerate() {
+        let digit_rect = rect!(
+            rect.x() + (i*COUNTER_WIDTH) as i32,
+            rect.y(),
+            COUNTER_WIDTH,
+            COUNTER_HEIGHT
+        );
+        match digit {
+            Some(d) => spritesheet.draw(canvas, Sprite::Counter(*d), digit_rect)?,
+            None => {
+                // there is no sprite for the minus sign
+                let color = canvas.draw_color();
+                canvas.set_draw_color(Color::RGB(0, 0, 0));
+                canvas.fill_rect(digit_rect)?;
+                canvas.set_draw_color(Color::RGB(255, 0, 0));
+                canvas.fill_rect(rect!(
+                    digit_rect.x() + 2,
+                    digit_rect.y() + (COUNTER_HEIGHT/2) as i32 - 1,
+                    COUNTER_WIDTH - 4,
+                    3
+                ))?;
+                canvas.set_draw_color(color);
+            },
+        }
+    }
+    Ok(())
+}
@@ -0,0 +1,666 @@
+//! Recordings of games, input by input.
+//!
+//! A replay is a text file. The first line is `rustsweeper replay 1`, where
+//! the number is [`REPLAY_VERSION`]. Then come these lines, in this order:
+//!
+//! ```text
+//! size 9 9
+//! mines 0,3 4,4 8,1
+//! result won
+//! time 14250
+//! opened 71
+//! ```
+//!
+//! `size` is the width and height of the board, followed by its
+//! [`Topology`] when that isn't the default, as in `size 9 9 wrap both`, and
+//! then by `mines-per-square` and the most mines a square can hold when that
+//! is more than one. `mines` lists the squares with mines on them as `x,y`,
+//! a square appearing once for each mine it holds, and `result` is `won` or
+//! `lost`. `time` is how long the game took by its timer and `opened` how
+//! many squares were open at the end, for checking a replay that is played
+//! back.
+//!
+//! After those, each line is one input, starting with the milliseconds
+//! since recording started:
+//!
+//! ```text
+//! 1200 move 131 88
+//! 1310 down left 131 88
+//! 1402 up left 131 88
+//! 2050 key move -1 0
+//! 2080 key open
+//! ```
+//!
+//! `move`, `down` and `up` are the mouse, in pixels from the top left of the
+//! window, with `left` or `right` for the button. `key` is a key acting on
+//! the board: `move` with how far across and down, `open`, `flag`, `chord`
+//! or `restart`.
+
+use std::fmt;
+use std::fs;
+use std::io;
+use std::path::{ Path, PathBuf };
+use std::str::FromStr;
+use std::time::{ Duration, Instant, SystemTime, UNIX_EPOCH };
+
+use crate::*;
+
+/// The version of the replay format that is written and can be read
+pub const REPLAY_VERSION: u32 = 1;
+
+/// An input that reached the board or the face button
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ReplayInput {
+    /// The mouse moved to `(x, y)`, in pixels
+    Motion(i32, i32),
+    /// A mouse button went down or up at `(x, y)`, in pixels
+    Click(InputAction, i32, i32),
+    /// A key acted on the board
+    Key(KeyAction),
+}
+
+/// An input and when it happened
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ReplayEvent {
+    /// The time since recording started
+    pub at: Duration,
+    pub input: ReplayInput,
+}
+
+/// How a recorded game ended
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Outcome {
+    Won,
+    Lost,
+}
+
+/// A whole game: the board it was played on, the inputs and how it ended
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Replay {
+    pub width: usize,
+    pub height: usize,
+    pub topology: Topology,
+    /// The most mines a single square can hold
+    pub mines_per_square: u8,
+    /// Every mine, so a square holding several is listed several times
+    pub mines: Vec<(usize, usize)>,
+    pub outcome: Outcome,
+    /// How long the game took by its timer
+    pub time: Duration,
+    /// How many squares were open at the end
+    pub opened: usize,
+    pub events: Vec<ReplayEvent>,
+}
+
+/// Why a replay couldn't be read
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ReplayError {
+    /// The first line isn't a replay header for a version we can read
+    Header(String),
+    /// A line is missing from the header
+    Missing(&'static str),
+    /// A line couldn't be read, counting from 1
+    Line { line: usize, text: String },
+}
+impl fmt::Display for ReplayError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ReplayError::Header(header) => write!(
+                f, "{:?} is not a replay header, expected \"rustsweeper replay {}\"",
+                header, REPLAY_VERSION
+            ),
+            ReplayError::Missing(name) => write!(f, "the replay has no {} line", name),
+            ReplayError::Line { line, text } => write!(
+                f, "line {} of the replay can't be read: {:?}", line, text
+            ),
+        }
+    }
+}
+impl std::error::Error for ReplayError {}
+
+impl Replay {
+    /// Writes the replay to a new file in `dir`, named after the time,
+    /// returning the path it was written to.
+    pub fn save_in(&self, dir: &Path) -> io::Result<PathBuf> {
+        let millis = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .unwrap_or_default()
+            .as_millis();
+        fs::create_dir_all(dir)?;
+        let path = dir.join(format!("{}.replay", millis));
+        fs::write(&path, self.to_string())?;
+        Ok(path)
+    }
+
+    pub fn load(path: &Path) -> Result<Self, String> {
+        let text = fs::read_to_string(path)
+            .map_err(|e| format!("can't read {}: {}", path.display(), e))?;
+        text.parse()
+            .map_err(|e: ReplayError| format!("{}: {}", path.display(), e))
+    }
+}
+
+impl fmt::Display for Replay {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(f, "rustsweeper replay {}", REPLAY_VERSION)?;
+        write!(f, "size {} {}", self.width, self.height)?;
+        if !self.topology.is_default() {
+            write!(f, " {}", self.topology)?;
+        }
+        if self.mines_per_square > 1 {
+            write!(f, " mines-per-square {}", self.mines_per_square)?;
+        }
+        writeln!(f)?;
+        write!(f, "mines")?;
+        for (x, y) in &self.mines {
+            write!(f, " {},{}", x, y)?;
+        }
+        writeln!(f)?;
+        let outcome = match self.outcome {
+            Outcome::Won => "won",
+            Outcome::Lost => "lost",
+        };
+        writeln!(f, "result {}", outcome)?;
+        writeln!(f, "time {}", self.time.as_millis())?;
+        writeln!(f, "opened {}", self.opened)?;
+
+        for event in &self.events {
+            write!(f, "{} ", event.at.as_millis())?;
+            match event.input {
+                ReplayInput::Motion(x, y) => writeln!(f, "move {} {}", x, y)?,
+                ReplayInput::Click(input_action, x, y) => {
+                    let edge = match input_action {
+                        InputAction::LeftDown => "down left",
+                        InputAction::LeftUp => "up left",
+                        InputAction::RightDown => "down right",
+                        InputAction::RightUp => "up right",
+                        InputAction::None => "none",
+                    };
+                    writeln!(f, "{} {} {}", edge, x, y)?
+                },
+                ReplayInput::Key(key_action) => match key_action {
+                    KeyAction::Move(dx, dy) => writeln!(f, "key move {} {}", dx, dy)?,
+                    KeyAction::Open => writeln!(f, "key open")?,
+                    KeyAction::Flag => writeln!(f, "key flag")?,
+                    KeyAction::Chord => writeln!(f, "key chord")?,
+                    KeyAction::Restart => writeln!(f, "key restart")?,
+                },
+            }
+        }
+        Ok(())
+    }
+}
+
+impl FromStr for Replay {
+    type Err = ReplayError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut lines = s.lines().enumerate().map(|(i, line)| (i + 1, line));
+        let header = lines.next().map(|(_, line)| line).unwrap_or_default();
+        if header != format!("rustsweeper replay {}", REPLAY_VERSION) {
+            return Err(ReplayError::Header(header.to_string()));
+        }
+
+        // each header line is a name followed by its values
+        let mut field = |name: &'static str| {
+            let (i, line) = lines.next().ok_or(ReplayError::Missing(name))?;
+            let bad = || ReplayError::Line { line: i, text: line.to_string() };
+            let mut words = line.split_whitespace();
+            if words.next() != Some(name) {
+                return Err(bad());
+            }
+            Ok((words.map(String::from).collect::<Vec<_>>(), bad()))
+        };
+
+        let (size, bad) = field("size")?;
+        let (size, mines_per_square) = match &size[..] {
+            [size @ .., name, n] if name == "mines-per-square" => (size, number(n, &bad)?),
+            size => (size, 1),
+        };
+        let (width, height, topology) = match size {
+            [width, height, topology @ ..] => (
+                number(width, &bad)?,
+                number(height, &bad)?,
+                number::<Topology>(&topology.join(" "), &bad)?,
+            ),
+            _ => return Err(bad),
+        };
+        if !(1..=topology.max_mines_per_square()).contains(&mines_per_square) {
+            return Err(bad);
+        }
+        let (mines, bad) = field("mines")?;
+        let mines = mines.iter()
+            .map(|square| {
+                let (x, y) = square.split_once(',').ok_or_else(|| bad.clone())?;
+                let (x, y) = (number(x, &bad)?, number(y, &bad)?);
+                if x < width && y < height { Ok((x, y)) } else { Err(bad.clone()) }
+            })
+            .collect::<Result<_, _>>()?;
+        let (outcome, bad) = field("result")?;
+        let outcome = match outcome.iter().map(String::as_str).collect::<Vec<_>>()[..] {
+            ["won"] => Outcome::Won,
+            ["lost"] => Outcome::Lost,
+            _ => return Err(bad),
+        };
+        let (time, bad) = field("time")?;
+        let time = match &time[..] {
+            [time] => Duration::from_millis(number(time, &bad)?),
+            _ => return Err(bad),
+        };
+        let (opened, bad) = field("opened")?;
+        let opened = match &opened[..] {
+            [opened] => number(opened, &bad)?,
+            _ => return Err(bad),
+        };
+
+        let events = lines
+            .filter(|(_, line)| !line.trim().is_empty())
+            .map(|(i, line)| parse_event(line).ok_or(ReplayError::Line {
+                line: i,
+                text: line.to_string(),
+            }))
+            .collect::<Result<_, _>>()?;
+
+        Ok(Replay { width, height, topology, mines_per_square, mines, outcome, time, opened, events })
+    }
+}
+
+fn number<T: FromStr>(s: &str, bad: &ReplayError) -> Result<T, ReplayError> {
+    s.parse().map_err(|_| bad.clone())
+}
+
+fn parse_event(line: &str) -> Option<ReplayEvent> {
+    let words: Vec<_> = line.split_whitespace().collect();
+    let (at, input) = words.split_first()?;
+    let at = Duration::from_millis(at.parse().ok()?);
+    let input = match *input {
+        ["move", x, y] => ReplayInput::Motion(x.parse().ok()?, y.parse().ok()?),
+        [edge @ ("down" | "up"), button, x, y] => {
+            let input_action = match (edge, button) {
+                ("down", "left") => InputAction::LeftDown,
+                ("up", "left") => InputAction::LeftUp,
+                ("down", "right") => InputAction::RightDown,
+                ("up", "right") => InputAction::RightUp,
+                _ => return None,
+            };
+            ReplayInput::Click(input_action, x.parse().ok()?, y.parse().ok()?)
+        },
+        ["none", x, y] => ReplayInput::Click(InputAction::None, x.parse().ok()?, y.parse().ok()?),
+        ["key", "move", dx, dy] => ReplayInput::Key(KeyAction::Move(dx.parse().ok()?, dy.parse().ok()?)),
+        ["key", "open"] => ReplayInput::Key(KeyAction::Open),
+        ["key", "flag"] => ReplayInput::Key(KeyAction::Flag),
+        ["key", "chord"] => ReplayInput::Key(KeyAction::Chord),
+        ["key", "restart"] => ReplayInput::Key(KeyAction::Restart),
+        _ => return None,
+    };
+    Some(ReplayEvent { at, input })
+}
+
+/// Collects the inputs of a game as it is played
+pub struct Recorder {
+    started: Instant,
+    events: Vec<ReplayEvent>,
+}
+impl Recorder {
+    pub fn new() -> Self {
+        Self {
+            started: Instant::now(),
+            events: vec![],
+        }
+    }
+
+    /// Throws away everything recorded so far, for a new game.
+    pub fn restart(&mut self) {
+        *self = Self::new();
+    }
+
+    pub fn record(&mut self, input: ReplayInput) {
+        if let ReplayInput::Click(InputAction::None, ..) = input {
+            return;
+        }
+        self.events.push(ReplayEvent { at: self.started.elapsed(), input });
+    }
+
+    /// Turns the inputs so far into a replay of `game`, which has just
+    /// ended, and starts recording the next one.
+    pub fn finish(&mut self, game: &Game) -> Replay {
+        let board = game.board();
+        let mines = (0..board.height())
+            .flat_map(|y| (0..board.width()).map(move |x| (x, y)))
+            .flat_map(|p| std::iter::repeat_n(p, board[p].mines as usize))
+            .collect();
+        let outcome = match game.state() {
+            GameState::Win => Outcome::Won,
+            _ => Outcome::Lost,
+        };
+        let replay = Replay {
+            width: board.width(),
+            height: board.height(),
+            topology: board.topology(),
+            mines_per_square: board.mines_per_square(),
+            mines,
+            outcome,
+            time: game.timer().elapsed(),
+            opened: board.num_opened(),
+            events: std::mem::take(&mut self.events),
+        };
+        self.restart();
+        replay
+    }
+}
+impl Default for Recorder {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// The speeds a replay can be played back at
+pub const PLAYBACK_SPEEDS: [f64; 4] = [0.5, 1.0, 2.0, 8.0];
+
+/// How far a replay has got as it is played back
+pub struct Playback {
+    replay: Replay,
+    /// The time into the recording
+    position: Duration,
+    /// The index of the next input to play
+    next: usize,
+    pub speed: f64,
+    pub paused: bool,
+}
+impl Playback {
+    pub fn new(replay: Replay) -> Self {
+        Self {
+            replay,
+            position: Duration::ZERO,
+            next: 0,
+            speed: 1.0,
+            paused: false,
+        }
+    }
+
+    pub fn replay(&self) -> &Replay { &self.replay }
+
+    pub fn position(&self) -> Duration { self.position }
+
+    /// When the last input happened
+    pub fn length(&self) -> Duration {
+        self.replay.events.last().map_or(Duration::ZERO, |event| event.at)
+    }
+
+    /// Whether every input has been played.
+    pub fn is_finished(&self) -> bool {
+        self.next == self.replay.events.len()
+    }
+
+    /// A game on the board the replay was played on, for the inputs to be
+    /// played on from the start.
+    pub fn game(&self) -> Game {
+        let replay = &self.replay;
+        Game::with_mines(
+            replay.width,
+            replay.height,
+            replay.topology,
+            replay.mines_per_square,
+            replay.mines.clone(),
+        )
+    }
+
+    /// Moves on by `elapsed` real time at the playback speed, unless paused,
+    /// returning the inputs that came due.
+    pub fn advance(&mut self, elapsed: Duration) -> &[ReplayEvent] {
+        if !self.paused {
+            self.position = (self.position + elapsed.mul_f64(self.speed)).min(self.length());
+        }
+        self.play_until(self.position)
+    }
+
+    /// Plays just the next input, jumping ahead to when it happened.
+    pub fn step(&mut self) -> Option<ReplayEvent> {
+        let event = *self.replay.events.get(self.next)?;
+        self.next += 1;
+        self.position = event.at;
+        Some(event)
+    }
+
+    /// Jumps to `position`, returning every input up to it. They are to be
+    /// played on a new game from [`Playback::game`].
+    pub fn seek(&mut self, position: Duration) -> &[ReplayEvent] {
+        self.position = position.min(self.length());
+        self.next = 0;
+        self.play_until(self.position)
+    }
+
+    fn play_until(&mut self, position: Duration) -> &[ReplayEvent] {
+        let start = self.next;
+        let events = &self.replay.events;
+        while self.next < events.len() && events[self.next].at <= position {
+            self.next += 1;
+        }
+        &events[start..self.next]
+    }
+
+    /// How `game`, played from the inputs so far, differs from the
+    /// recording. Games end on their last input, so until then the only
+    /// difference can be ending early.
+    pub fn divergence(&self, game: &Game) -> Option<Divergence> {
+        let played = match game.state() {
+            GameState::Win => Some(Outcome::Won),
+            GameState::Lose => Some(Outcome::Lost),
+            _ => None,
+        };
+        if !self.is_finished() {
+            return played.map(|_| Divergence::EndedEarly {
+                remaining: self.replay.events.len() - self.next,
+            });
+        }
+        if played != Some(self.replay.outcome) {
+            return Some(Divergence::Outcome { recorded: self.replay.outcome, played });
+        }
+        let opened = game.board().num_opened();
+        if opened != self.replay.opened {
+            return Some(Divergence::Opened { recorded: self.replay.opened, played: opened });
+        }
+        None
+    }
+}
+
+/// A way a played back game doesn't match its recording
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Divergence {
+    /// The game ended with inputs still to play
+    EndedEarly { remaining: usize },
+    /// The game ended differently, or didn't end
+    Outcome { recorded: Outcome, played: Option<Outcome> },
+    /// A different number of squares were open at the end
+    Opened { recorded: usize, played: usize },
+}
+impl fmt::Display for Divergence {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Divergence::EndedEarly { remaining } => write!(
+                f, "the game ended with {} inputs left to play", remaining
+            ),
+            Divergence::Outcome { recorded, played } => write!(
+                f, "the recorded game was {:?} but the played back game was {}",
+                recorded,
+                played.map_or("unfinished".to_string(), |played| format!("{:?}", played)),
+            ),
+            Divergence::Opened { recorded, played } => write!(
+                f, "{} squares were open in the recorded game but {} in the played back game",
+                recorded, played
+            ),
+        }
+    }
+}
+impl std::error::Error for Divergence {}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn replay() -> Replay {
+        let event = |ms, input| ReplayEvent { at: Duration::from_millis(ms), input };
+        Replay {
+            width: 4,
+            height: 3,
+            topology: Topology::default(),
+            mines_per_square: 1,
+            mines: vec![(0, 0), (3, 2)],
+            outcome: Outcome::Lost,
+            time: Duration::from_millis(2500),
+            opened: 1,
+            events: vec![
+                event(0, ReplayInput::Motion(40, 50)),
+                event(10, ReplayInput::Click(InputAction::LeftDown, 40, 50)),
+                event(25, ReplayInput::Click(InputAction::LeftUp, 40, 50)),
+                event(900, ReplayInput::Key(KeyAction::Move(-1, 0))),
+                event(2500, ReplayInput::Key(KeyAction::Open)),
+            ],
+        }
+    }
+
+    #[test]
+    pub fn round_trip() {
+        let text = replay().to_string();
+        assert!(text.starts_with("rustsweeper replay 1\nsize 4 3\nmines 0,0 3,2\n"));
+        assert!(text.contains("\n10 down left 40 50\n"));
+        assert!(text.contains("\n900 key move -1 0\n"));
+        assert_eq!(text.parse(), Ok(replay()));
+
+        let topology = "grid hex wrap both".parse().unwrap();
+        let hex = Replay { topology, ..replay() };
+        let text = hex.to_string();
+        assert!(text.contains("\nsize 4 3 grid hex wrap both\n"));
+        assert_eq!(text.parse(), Ok(hex));
+
+        let stacked = Replay { mines_per_square: 3, mines: vec![(1, 1), (1, 1)], ..replay() };
+        let text = stacked.to_string();
+        assert!(text.contains("\nsize 4 3 mines-per-square 3\nmines 1,1 1,1\n"));
+        assert_eq!(text.parse(), Ok(stacked));
+    }
+
+    #[test]
+    pub fn errors() {
+        assert!(matches!(
+            "rustsweeper replay 99\n".parse::<Replay>(),
+            Err(ReplayError::Header(_)),
+        ));
+        assert_eq!(
+            "rustsweeper replay 1\nsize 4 3\n".parse::<Replay>(),
+            Err(ReplayError::Missing("mines")),
+        );
+
+        let text = replay().to_string().replace("mines 0,0 3,2", "mines 0,0 4,2");
+        assert!(matches!(text.parse::<Replay>(), Err(ReplayError::Line { line: 3, .. })));
+        // five mines to a square could put 120 around one with radius2
+        let text = replay().to_string()
+            .replace("size 4 3", "size 4 3 neighborhood radius2 mines-per-square 5");
+        assert!(matches!(text.parse::<Replay>(), Err(ReplayError::Line { line: 2, .. })));
+        let text = replay().to_string().replace("key open", "key jump");
+        assert!(matches!(text.parse::<Replay>(), Err(ReplayError::Line { line: 11, .. })));
+    }
+
+    #[test]
+    pub fn record() {
+        let mut game = Game::new(Settings {
+            difficulty: Difficulty::Beginner,
+            seed: Some(3),
+            ..Settings::default()
+        });
+        let mut recorder = Recorder::new();
+        recorder.record(ReplayInput::Key(KeyAction::Open));
+        game.apply(Action::Open(0, 0));
+        let board = game.board();
+        let mine = (0..board.height())
+            .flat_map(|y| (0..board.width()).map(move |x| (x, y)))
+            .find(|&p| board[p].is_mine())
+            .unwrap();
+        recorder.record(ReplayInput::Key(KeyAction::Move(mine.0 as isize, mine.1 as isize)));
+        recorder.record(ReplayInput::Key(KeyAction::Open));
+        game.apply(Action::Open(mine.0, mine.1));
+
+        let replay = recorder.finish(&game);
+        assert_eq!(replay.outcome, Outcome::Lost);
+        assert_eq!((replay.width, replay.height, replay.mines.len()), (9, 9, 10));
+        assert!(replay.mines.contains(&mine));
+        assert_eq!(replay.events.len(), 3);
+        assert!(recorder.events.is_empty());
+    }
+
+    /// Plays keyboard inputs on `game`, as a frontend would.
+    fn play(game: &mut Game, cursor: &mut KeyCursor, events: &[ReplayEvent]) {
+        for event in events {
+            if let ReplayInput::Key(key_action) = event.input {
+                let action = cursor.transition(key_action, game.board());
+                game.apply(action);
+            }
+        }
+    }
+
+    /// A lost game, recorded: open the middle, then a mine next to it.
+    fn recorded() -> Replay {
+        let mut game = Game::with_mines(3, 3, Topology::default(), 1, vec![(0, 0), (2, 2)]);
+        let mut cursor = KeyCursor::default();
+        let mut recorder = Recorder::new();
+        for key_action in [KeyAction::Move(1, 1), KeyAction::Open, KeyAction::Move(1, 1), KeyAction::Open] {
+            recorder.record(ReplayInput::Key(key_action));
+            let action = cursor.transition(key_action, game.board());
+            game.apply(action);
+        }
+        recorder.finish(&game)
+    }
+
+    #[test]
+    pub fn playback() {
+        let replay = recorded();
+        assert_eq!((replay.outcome, replay.opened), (Outcome::Lost, 1));
+
+        let mut playback = Playback::new(replay.clone());
+        let mut game = playback.game();
+        let mut cursor = KeyCursor::default();
+        playback.paused = true;
+        assert!(playback.advance(Duration::from_secs(1)).is_empty());
+
+        let event = playback.step().unwrap();
+        play(&mut game, &mut cursor, &[event]);
+        assert_eq!(playback.position(), event.at);
+        assert_eq!(playback.divergence(&game), None);
+
+        playback.paused = false;
+        let events = playback.advance(Duration::from_secs(1)).to_vec();
+        assert_eq!(events.len(), 3);
+        play(&mut game, &mut cursor, &events);
+        assert!(playback.is_finished());
+        assert_eq!(playback.divergence(&game), None);
+
+        // seeking back plays everything again on a new game
+        let events = playback.seek(Duration::ZERO).to_vec();
+        assert_eq!(events.len(), replay.events.iter().filter(|e| e.at.is_zero()).count());
+        let mut game = playback.game();
+        let mut cursor = KeyCursor::default();
+        play(&mut game, &mut cursor, &events);
+        assert_eq!(playback.divergence(&game), None);
+    }
+
+    #[test]
+    pub fn divergence() {
+        let mut replay = recorded();
+        replay.opened = 5;
+        let mut playback = Playback::new(replay.clone());
+        let mut game = playback.game();
+        let events = playback.seek(Duration::MAX).to_vec();
+        play(&mut game, &mut KeyCursor::default(), &events);
+        assert_eq!(playback.divergence(&game), Some(Divergence::Opened { recorded: 5, played: 1 }));
+
+        // the mine is opened one input early
+        replay.events.remove(2);
+        let mut playback = Playback::new(replay);
+        let mut game = playback.game();
+        let events = playback.seek(Duration::MAX).to_vec();
+        play(&mut game, &mut KeyCursor::default(), &events);
+        assert_eq!(
+            playback.divergence(&game),
+            Some(Divergence::Outcome { recorded: Outcome::Lost, played: None }),
+        );
+    }
+}
@@ -0,0 +1,344 @@
+//! Games saved part way through, to be picked up again later.
+//!
+//! A save is a text file. The first line is `rustsweeper save 1`, where the
+//! number is [`SAVE_VERSION`], followed by:
+//!
+//! ```text
+//! difficulty expert
+//! seed 1234
+//! no-guess no
+//! time 81250
+//! clicks 40 12 9
+//! hints 1
+//! ```
+//!
+//! `difficulty` is followed by the board's [`Topology`] when it isn't the
+//! default, as in `difficulty expert grid hex wrap both`, and then by
+//! `mines-per-square` and the most mines a square can hold when that is
+//! more than one. `seed` is `none` for a board that wasn't seeded, `time`
+//! is milliseconds on the timer and `clicks` are the open, flag and chord
+//! clicks made. The rest of the file is the board, written as by
+//! [`Board`]'s `Display`.
+
+use std::fmt;
+use std::fs;
+use std::io;
+use std::path::{ Path, PathBuf };
+use std::str::FromStr;
+use std::time::Duration;
+
+use crate::*;
+
+/// The version of the save format that is written and can be read
+pub const SAVE_VERSION: u32 = 1;
+
+/// Where saves, scores and the like are kept, if there is anywhere.
+pub fn data_dir() -> Option<PathBuf> {
+    dirs::data_dir().map(|dir| dir.join("rustsweeper"))
+}
+
+/// A game in progress, as it is saved
+#[derive(Debug, Clone)]
+pub struct SavedGame {
+    pub settings: Settings,
+    pub board: Board,
+    /// The time on the timer
+    pub elapsed: Duration,
+    pub clicks: Clicks,
+    pub hints_used: usize,
+}
+
+/// Why a save couldn't be read
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum SaveError {
+    /// The first line isn't a save header for a version we can read
+    Header(String),
+    /// A line is missing
+    Missing(&'static str),
+    /// A line couldn't be read, counting from 1
+    Line { line: usize, text: String },
+    /// The board couldn't be read
+    Board(ParseBoardError),
+    /// The board is a different size to the difficulty
+    Size { difficulty: Difficulty, width: usize, height: usize },
+}
+impl fmt::Display for SaveError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            SaveError::Header(header) => write!(
+                f, "{:?} is not a save header, expected \"rustsweeper save {}\"",
+                header, SAVE_VERSION
+            ),
+            SaveError::Missing(name) => write!(f, "the save has no {} line", name),
+            SaveError::Line { line, text } => write!(
+                f, "line {} of the save can't be read: {:?}", line, text
+            ),
+            SaveError::Board(e) => write!(f, "the saved board can't be read: {}", e),
+            SaveError::Size { difficulty, width, height } => write!(
+                f, "the saved board is {}x{}, which doesn't fit {}", width, height, difficulty
+            ),
+        }
+    }
+}
+impl std::error::Error for SaveError {}
+
+impl SavedGame {
+    /// What there is to save of `game`, if it is being played.
+    pub fn of(game: &Game) -> Option<Self> {
+        if !matches!(game.state(), GameState::Play) {
+            return None;
+        }
+        Some(Self {
+            settings: *game.settings(),
+            board: game.board().clone(),
+            elapsed: game.timer().elapsed(),
+            clicks: *game.clicks(),
+            hints_used: game.hints_used(),
+        })
+    }
+
+    pub fn save(&self, path: &Path) -> io::Result<()> {
+        if let Some(dir) = path.parent() {
+            fs::create_dir_all(dir)?;
+        }
+        fs::write(path, self.to_string())
+    }
+
+    pub fn load(path: &Path) -> Result<Self, String> {
+        let text = fs::read_to_string(path)
+            .map_err(|e| format!("can't read {}: {}", path.display(), e))?;
+        text.parse()
+            .map_err(|e: SaveError| format!("{}: {}", path.display(), e))
+    }
+}
+
+impl fmt::Display for SavedGame {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(f, "rustsweeper save {}", SAVE_VERSION)?;
+        write!(f, "difficulty {}", self.settings.difficulty)?;
+        if !self.settings.topology.is_default() {
+            write!(f, " {}", self.settings.topology)?;
+        }
+        if self.settings.mines_per_square > 1 {
+            write!(f, " mines-per-square {}", self.settings.mines_per_square)?;
+        }
+        writeln!(f)?;
+        match self.settings.seed {
+            Some(seed) => writeln!(f, "seed {}", seed)?,
+            None => writeln!(f, "seed none")?,
+        }
+        writeln!(f, "no-guess {}", if self.settings.no_guess { "yes" } else { "no" })?;
+        writeln!(f, "time {}", self.elapsed.as_millis())?;
+        let clicks = self.clicks;
+        writeln!(f, "clicks {} {} {}", clicks.open, clicks.flag, clicks.chord)?;
+        writeln!(f, "hints {}", self.hints_used)?;
+        write!(f, "{}", self.board)
+    }
+}
+
+impl FromStr for SavedGame {
+    type Err = SaveError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut lines = s.lines().enumerate().map(|(i, line)| (i + 1, line));
+        let header = lines.next().map(|(_, line)| line).unwrap_or_default();
+        if header != format!("rustsweeper save {}", SAVE_VERSION) {
+            return Err(SaveError::Header(header.to_string()));
+        }
+
+        // each line is a name followed by its values
+        let mut field = |name: &'static str| {
+            let (i, line) = lines.next().ok_or(SaveError::Missing(name))?;
+            let bad = SaveError::Line { line: i, text: line.to_string() };
+            let mut words = line.split_whitespace();
+            if words.next() != Some(name) {
+                return Err(bad);
+            }
+            Ok((words.map(String::from).collect::<Vec<_>>(), bad))
+        };
+
+        let (values, bad) = field("difficulty")?;
+        let (values, mines_per_square) = match &values[..] {
+            [values @ .., name, n] if name == "mines-per-square" => {
+                (values, n.parse().map_err(|_| bad.clone())?)
+            },
+            values => (values, 1),
+        };
+        let difficulty: Difficulty = value(&values[..values.len().min(1)], &bad)?;
+        let topology: Topology = values[1..].join(" ").parse().map_err(|_| bad.clone())?;
+        if !(1..=topology.max_mines_per_square()).contains(&mines_per_square) {
+            return Err(bad);
+        }
+        let difficulty_bad = bad;
+        let (values, bad) = field("seed")?;
+        let seed = match &values[..] {
+            [none] if none == "none" => None,
+            _ => Some(value(&values, &bad)?),
+        };
+        let (values, bad) = field("no-guess")?;
+        let no_guess = match &values[..] {
+            [yes] if yes == "yes" => true,
+            [no] if no == "no" => false,
+            _ => return Err(bad),
+        };
+        let (values, bad) = field("time")?;
+        let elapsed = Duration::from_millis(value(&values, &bad)?);
+        let (values, bad) = field("clicks")?;
+        let clicks = match &values[..] {
+            [open, flag, chord] => Clicks {
+                open: open.parse().map_err(|_| bad.clone())?,
+                flag: flag.parse().map_err(|_| bad.clone())?,
+                chord: chord.parse().map_err(|_| bad.clone())?,
+            },
+            _ => return Err(bad),
+        };
+        let (values, bad) = field("hints")?;
+        let hints_used = value(&values, &bad)?;
+
+        let rest: Vec<_> = lines.map(|(_, line)| line).collect();
+        let mut board = Board::from_text(&rest.join("\n"), topology)
+            .map_err(|e: ParseBoardError| SaveError::Board(ParseBoardError {
+                // count lines from the top of the file
+                line: e.line + 7,
+                ..e
+            }))?;
+        if (board.width(), board.height()) != (difficulty.width(), difficulty.height()) {
+            return Err(SaveError::Size { difficulty, width: board.width(), height: board.height() });
+        }
+        if board.mines_per_square() > mines_per_square {
+            return Err(difficulty_bad);
+        }
+        board.set_seed(seed);
+        board.set_mines_per_square(mines_per_square);
+
+        Ok(SavedGame {
+            settings: Settings { difficulty, seed, no_guess, topology, mines_per_square },
+            board,
+            elapsed,
+            clicks,
+            hints_used,
+        })
+    }
+}
+
+/// Reads a line's only value.
+fn value<T: FromStr>(values: &[String], bad: &SaveError) -> Result<T, SaveError> {
+    match values {
+        [value] => value.parse().map_err(|_| bad.clone()),
+        _ => Err(bad.clone()),
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn played() -> Game {
+        let mut game = Game::new(Settings {
+            difficulty: Difficulty::Beginner,
+            seed: Some(3),
+            ..Settings::default()
+        });
+        game.apply(Action::Open(4, 4));
+        game.apply(Action::Flag(0, 0));
+        game
+    }
+
+    #[test]
+    pub fn round_trip() {
+        let game = played();
+        let saved = SavedGame::of(&game).unwrap();
+        let text = saved.to_string();
+        assert!(text.starts_with("rustsweeper save 1\ndifficulty beginner\nseed 3\nno-guess no\n"));
+
+        let read: SavedGame = text.parse().unwrap();
+        assert_eq!(read.to_string(), text);
+        assert_eq!(read.settings, *game.settings());
+        assert_eq!(read.board.seed(), Some(3));
+
+        let elapsed = read.elapsed;
+        assert_eq!(elapsed.as_millis(), saved.elapsed.as_millis());
+        let resumed = Game::resume(read);
+        assert!(matches!(resumed.state(), GameState::Play));
+        assert!(resumed.timer().is_running());
+        assert!(resumed.timer().elapsed() >= elapsed);
+        assert_eq!(resumed.mines_left(), 9);
+        assert_eq!(resumed.board().to_string(), game.board().to_string());
+        assert_eq!(resumed.clicks(), game.clicks());
+    }
+
+    #[test]
+    pub fn topology() {
+        let mut game = Game::new(Settings {
+            difficulty: Difficulty::Beginner,
+            seed: Some(3),
+            topology: "grid hex wrap horizontal".parse().unwrap(),
+            ..Settings::default()
+        });
+        game.apply(Action::Open(4, 4));
+        let text = SavedGame::of(&game).unwrap().to_string();
+        assert!(text.contains("\ndifficulty beginner grid hex wrap horizontal\n"));
+        // the numbers only add up with the same topology
+        let read: SavedGame = text.parse().unwrap();
+        assert_eq!(read.board.topology(), game.board().topology());
+        assert!(text.replace(" wrap horizontal", "").parse::<SavedGame>().is_err());
+        assert!(text.replace(" grid hex", "").parse::<SavedGame>().is_err());
+        assert!(text.replace("grid hex", "grid hex wrap").parse::<SavedGame>().is_err());
+    }
+
+    #[test]
+    pub fn several_mines() {
+        let mut game = Game::with_mines(3, 1, Topology::default(), 3, vec![(1, 0), (1, 0)]);
+        game.apply(Action::Open(0, 0));
+        game.apply(Action::Flag(1, 0));
+        let text = SavedGame::of(&game).unwrap().to_string();
+        assert!(text.contains("\ndifficulty 3x1x2 mines-per-square 3\n"));
+        assert!(text.ends_with("\n2F.\n\n1,0 mines 2 flags 1\n"));
+
+        let resumed = Game::resume(text.parse().unwrap());
+        assert_eq!(resumed.settings(), game.settings());
+        let cell = resumed.board()[(1, 0)];
+        assert_eq!((cell.mines, cell.flags, resumed.board().mines_per_square()), (2, 1, 3));
+        // more than the neighbourhood allows, or than the most to a square
+        let radius2 = text.replace("3x1x2", "3x1x2 neighborhood radius2")
+            .replace("mines-per-square 3", "mines-per-square 5");
+        assert!(radius2.parse::<SavedGame>().is_err());
+        let fewer = text.replace(" mines-per-square 3", "");
+        assert!(matches!(fewer.parse::<SavedGame>(), Err(SaveError::Line { line: 2, .. })));
+    }
+
+    #[test]
+    pub fn only_games_in_progress() {
+        assert!(SavedGame::of(&Game::new(Settings::default())).is_none());
+    }
+
+    #[test]
+    pub fn errors() {
+        let text = SavedGame::of(&played()).unwrap().to_string();
+        assert!(matches!(
+            text.replace("save 1", "save 2").parse::<SavedGame>(),
+            Err(SaveError::Header(_)),
+        ));
+        assert!(matches!(
+            text.replace("seed 3", "seed three").parse::<SavedGame>(),
+            Err(SaveError::Line { line: 3, .. }),
+        ));
+        assert_eq!(
+            "rustsweeper save 1\ndifficulty expert\n".parse::<SavedGame>().unwrap_err(),
+            SaveError::Missing("seed"),
+        );
+        assert!(matches!(
+            text.replace("difficulty beginner", "difficulty expert").parse::<SavedGame>(),
+            Err(SaveError::Size { width: 9, height: 9, .. }),
+        ));
+
+        // a square of the board that is cut off
+        let mut lines: Vec<_> = text.lines().collect();
+        let last = lines.pop().unwrap();
+        lines.push(&last[1..]);
+        assert!(matches!(
+            lines.join("\n").parse::<SavedGame>(),
+            Err(SaveError::Board(ParseBoardError { line: 16, .. })),
+        ));
+    }
+}
@@ -0,0 +1,395 @@
+//! The fastest wins, kept for each difficulty.
+//!
+//! Scores are a text file. The first line is `rustsweeper scores 1`, where
+//! the number is [`SCORES_VERSION`], then each line is one win:
+//!
+//! ```text
+//! expert 81250 1760745600 121 150 no-guess 0 JASPER
+//! 9x9x20 30120 1760832000 35 41 random 1 ANN
+//! ```
+//!
+//! That is the difficulty, the time in milliseconds, when the game was won
+//! in seconds since the Unix epoch, the board's 3BV, the clicks made,
+//! `no-guess` or `random` for how the board was generated, the hints used
+//! and last the name, which may have spaces in it.
+
+use std::fmt;
+use std::fs;
+use std::io;
+use std::path::Path;
+use std::str::FromStr;
+use std::time::{ Duration, SystemTime, UNIX_EPOCH };
+
+use crate::*;
+
+/// The version of the scores format that is written and can be read
+pub const SCORES_VERSION: u32 = 1;
+/// How many of the fastest wins are kept for each difficulty
+pub const SCORES_KEPT: usize = 10;
+/// The longest a name can be
+pub const NAME_LENGTH: usize = 8;
+
+/// A won game
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Score {
+    pub name: String,
+    pub difficulty: Difficulty,
+    pub time: Duration,
+    /// When the game was won, in seconds since the Unix epoch
+    pub date: u64,
+    pub bbbv: usize,
+    /// All the clicks made on the board
+    pub clicks: usize,
+    pub no_guess: bool,
+    pub hints: usize,
+}
+impl Score {
+    /// The score for `game` under `name`, if it was won on a board with the
+    /// classic topology and a mine at most on each square. Other boards play
+    /// too differently to compare.
+    pub fn of(game: &Game, name: &str) -> Option<Self> {
+        let board = game.board();
+        if !matches!(game.state(), GameState::Win)
+            || !board.topology().is_default()
+            || board.mines_per_square() > 1
+        {
+            return None;
+        }
+        let date = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .unwrap_or_default()
+            .as_secs();
+        Some(Self {
+            name: name.to_string(),
+            difficulty: game.difficulty(),
+            // to the millisecond, as it is saved
+            time: Duration::from_millis(game.timer().elapsed().as_millis() as u64),
+            date,
+            bbbv: metrics::bbbv(board),
+            clicks: game.clicks().total(),
+            no_guess: game.settings().no_guess,
+            hints: game.hints_used(),
+        })
+    }
+
+    /// 3BV as a percentage of the clicks made.
+    pub fn efficiency(&self) -> usize {
+        metrics::efficiency(self.bbbv, self.clicks)
+    }
+
+    /// The day the game was won, as `YYYY-MM-DD` in UTC.
+    pub fn day(&self) -> String {
+        let (year, month, day) = civil_date(self.date / 86400);
+        format!("{:04}-{:02}-{:02}", year, month, day)
+    }
+}
+
+/// The year, month and day `days` after 1970-01-01.
+fn civil_date(days: u64) -> (u64, u64, u64) {
+    // counting from 0000-03-01, so leap days fall at the end of a year
+    let days = days + 719468;
+    let era = days / 146097;
+    let day_of_era = days % 146097;
+    let year_of_era = (day_of_era - day_of_era/1460 + day_of_era/36524 - day_of_era/146096) / 365;
+    let day_of_year = day_of_era - (365*year_of_era + year_of_era/4 - year_of_era/100);
+    let shifted_month = (5*day_of_year + 2) / 153;
+    let day = day_of_year - (153*shifted_month + 2)/5 + 1;
+    let month = if shifted_month < 10 { shifted_month + 3 } else { shifted_month - 9 };
+    let year = era*400 + year_of_era + (month <= 2) as u64;
+    (year, month, day)
+}
+
+impl fmt::Display for Score {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f, "{} {} {} {} {} {} {} {}",
+            self.difficulty,
+            self.time.as_millis(),
+            self.date,
+            self.bbbv,
+            self.clicks,
+            if self.no_guess { "no-guess" } else { "random" },
+            self.hints,
+            self.name,
+        )
+    }
+}
+
+impl FromStr for Score {
+    type Err = ();
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut words = s.splitn(8, ' ');
+        let mut word = || words.next().ok_or(());
+        let difficulty = word()?.parse().map_err(|_| ())?;
+        let time = Duration::from_millis(word()?.parse().map_err(|_| ())?);
+        let date = word()?.parse().map_err(|_| ())?;
+        let bbbv = word()?.parse().map_err(|_| ())?;
+        let clicks = word()?.parse().map_err(|_| ())?;
+        let no_guess = match word()? {
+            "no-guess" => true,
+            "random" => false,
+            _ => return Err(()),
+        };
+        let hints = word()?.parse().map_err(|_| ())?;
+        let name = word()?.to_string();
+        Ok(Self { name, difficulty, time, date, bbbv, clicks, no_guess, hints })
+    }
+}
+
+/// Why scores couldn't be read
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ScoresError {
+    /// The first line isn't a scores header for a version we can read
+    Header(String),
+    /// A line couldn't be read, counting from 1
+    Line { line: usize, text: String },
+}
+impl fmt::Display for ScoresError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ScoresError::Header(header) => write!(
+                f, "{:?} is not a scores header, expected \"rustsweeper scores {}\"",
+                header, SCORES_VERSION
+            ),
+            ScoresError::Line { line, text } => write!(
+                f, "line {} of the scores can't be read: {:?}", line, text
+            ),
+        }
+    }
+}
+impl std::error::Error for ScoresError {}
+
+/// The fastest [`SCORES_KEPT`] wins for every difficulty that has any
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Scoreboard {
+    /// Fastest first within each difficulty
+    scores: Vec<Score>,
+}
+impl Scoreboard {
+    /// Reads the scores at `path`, which are empty if there is no file yet.
+    pub fn load(path: &Path) -> Result<Self, String> {
+        let text = match fs::read_to_string(path) {
+            Ok(text) => text,
+            Err(e) if e.kind() == io::ErrorKind::NotFound => return Ok(Self::default()),
+            Err(e) => return Err(format!("can't read {}: {}", path.display(), e)),
+        };
+        text.parse()
+            .map_err(|e: ScoresError| format!("{}: {}", path.display(), e))
+    }
+
+    pub fn save(&self, path: &Path) -> io::Result<()> {
+        if let Some(dir) = path.parent() {
+            fs::create_dir_all(dir)?;
+        }
+        fs::write(path, self.to_string())
+    }
+
+    /// The wins for `difficulty`, fastest first.
+    pub fn best(&self, difficulty: Difficulty) -> impl Iterator<Item=&Score> {
+        self.scores.iter().filter(move |score| score.difficulty == difficulty)
+    }
+
+    /// The name of the latest win, to offer for the next.
+    pub fn last_name(&self) -> Option<&str> {
+        self.scores.iter()
+            .max_by_key(|score| score.date)
+            .map(|score| score.name.as_str())
+    }
+
+    /// Every difficulty with scores: the presets, then custom boards from
+    /// the smallest.
+    pub fn difficulties(&self) -> Vec<Difficulty> {
+        let mut difficulties: Vec<_> = vec![];
+        for score in &self.scores {
+            if !difficulties.contains(&score.difficulty) {
+                difficulties.push(score.difficulty);
+            }
+        }
+        difficulties.sort_by_key(|difficulty| match *difficulty {
+            Difficulty::Beginner => (0, 0, 0, 0),
+            Difficulty::Intermediate => (1, 0, 0, 0),
+            Difficulty::Expert => (2, 0, 0, 0),
+            Difficulty::Custom { width, height, mines } => (3, width*height, mines, width),
+        });
+        difficulties
+    }
+
+    /// Where `score` would be placed among its difficulty's, counting from
+    /// 0, if it is fast enough to be kept. Ties go to the earlier win.
+    pub fn place(&self, score: &Score) -> Option<usize> {
+        let place = self.best(score.difficulty)
+            .take_while(|best| best.time <= score.time)
+            .count();
+        (place < SCORES_KEPT).then_some(place)
+    }
+
+    /// Keeps `score` if it is fast enough, returning its place.
+    pub fn insert(&mut self, score: Score) -> Option<usize> {
+        let place = self.place(&score)?;
+        let index = self.scores.iter()
+            .position(|best| best.difficulty == score.difficulty && best.time > score.time)
+            .unwrap_or(self.scores.len());
+        let difficulty = score.difficulty;
+        self.scores.insert(index, score);
+
+        // the one pushed off the end, if there was a full table
+        if let Some((index, _)) = self.scores.iter()
+            .enumerate()
+            .filter(|(_, best)| best.difficulty == difficulty)
+            .nth(SCORES_KEPT)
+        {
+            self.scores.remove(index);
+        }
+        Some(place)
+    }
+}
+
+impl fmt::Display for Scoreboard {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(f, "rustsweeper scores {}", SCORES_VERSION)?;
+        for score in &self.scores {
+            writeln!(f, "{}", score)?;
+        }
+        Ok(())
+    }
+}
+
+impl FromStr for Scoreboard {
+    type Err = ScoresError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut lines = s.lines();
+        let header = lines.next().unwrap_or_default();
+        if header != format!("rustsweeper scores {}", SCORES_VERSION) {
+            return Err(ScoresError::Header(header.to_string()));
+        }
+
+        let mut scoreboard = Self::default();
+        for (i, line) in lines.enumerate() {
+            if line.trim().is_empty() {
+                continue;
+            }
+            let score = line.parse()
+                .map_err(|_| ScoresError::Line { line: i + 2, text: line.to_string() })?;
+            // put back in order, in case the file was edited
+            scoreboard.insert(score);
+        }
+        Ok(scoreboard)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn score(name: &str, difficulty: Difficulty, millis: u64) -> Score {
+        Score {
+            name: name.to_string(),
+            difficulty,
+            time: Duration::from_millis(millis),
+            date: 1760745600,
+            bbbv: 30,
+            clicks: 40,
+            no_guess: false,
+            hints: 0,
+        }
+    }
+
+    #[test]
+    pub fn of() {
+        // won by opening both ends around the mine in the middle
+        let won = |topology: &str, mines_per_square| {
+            let topology = topology.parse().unwrap();
+            let mut game = Game::with_mines(3, 1, topology, mines_per_square, vec![(1, 0)]);
+            game.apply(Action::Open(0, 0));
+            game.apply(Action::Open(2, 0));
+            game
+        };
+        let score = Score::of(&won("", 1), "ann").unwrap();
+        assert_eq!((score.bbbv, score.clicks), (2, 2));
+        for topology in ["wrap horizontal", "grid hex", "neighborhood knight"] {
+            assert!(Score::of(&won(topology, 1), "ann").is_none());
+        }
+        assert!(Score::of(&won("", 2), "ann").is_none());
+    }
+
+    #[test]
+    pub fn places() {
+        let mut scoreboard = Scoreboard::default();
+        for i in 0..SCORES_KEPT as u64 {
+            assert_eq!(
+                scoreboard.insert(score("A", Difficulty::Beginner, 10_000 + i*1000)),
+                Some(i as usize),
+            );
+        }
+        // too slow to be kept
+        let slow = score("B", Difficulty::Beginner, 60_000);
+        assert_eq!(scoreboard.place(&slow), None);
+        assert_eq!(scoreboard.insert(slow), None);
+        // a tie goes after the earlier win, and pushes the slowest out
+        assert_eq!(scoreboard.insert(score("C", Difficulty::Beginner, 11_000)), Some(2));
+        let names: Vec<_> = scoreboard.best(Difficulty::Beginner)
+            .map(|score| score.name.as_str())
+            .collect();
+        assert_eq!(names.len(), SCORES_KEPT);
+        assert_eq!(&names[..3], ["A", "A", "C"]);
+        assert_eq!(
+            scoreboard.best(Difficulty::Beginner).last().unwrap().time,
+            Duration::from_millis(18_000),
+        );
+        // other difficulties have their own tables
+        assert_eq!(scoreboard.insert(score("D", Difficulty::Expert, 90_000)), Some(0));
+    }
+
+    #[test]
+    pub fn difficulties() {
+        let mut scoreboard = Scoreboard::default();
+        let big = Difficulty::custom(20, 20, 50).unwrap();
+        let small = Difficulty::custom(5, 5, 3).unwrap();
+        for difficulty in [big, Difficulty::Expert, small, Difficulty::Beginner] {
+            scoreboard.insert(score("A", difficulty, 1000));
+        }
+        assert_eq!(
+            scoreboard.difficulties(),
+            vec![Difficulty::Beginner, Difficulty::Expert, small, big],
+        );
+    }
+
+    #[test]
+    pub fn text_round_trip() {
+        let mut scoreboard = Scoreboard::default();
+        scoreboard.insert(score("JASPER T", Difficulty::Expert, 81_250));
+        let mut hinted = score("ANN", Difficulty::custom(9, 9, 20).unwrap(), 30_120);
+        hinted.no_guess = true;
+        hinted.hints = 2;
+        scoreboard.insert(hinted);
+
+        let text = scoreboard.to_string();
+        assert_eq!(
+            text,
+            "rustsweeper scores 1\n\
+             expert 81250 1760745600 30 40 random 0 JASPER T\n\
+             9x9x20 30120 1760745600 30 40 no-guess 2 ANN\n",
+        );
+        assert_eq!(text.parse::<Scoreboard>().unwrap(), scoreboard);
+
+        assert!(matches!(
+            "rustsweeper scores 2\n".parse::<Scoreboard>(),
+            Err(ScoresError::Header(_)),
+        ));
+        assert!(matches!(
+            text.replace("random", "lucky").parse::<Scoreboard>(),
+            Err(ScoresError::Line { line: 2, .. }),
+        ));
+    }
+
+    #[test]
+    pub fn efficiency_and_day() {
+        let score = score("A", Difficulty::Beginner, 1000);
+        assert_eq!(score.efficiency(), 75);
+        assert_eq!(score.day(), "2025-10-18");
+        assert_eq!(civil_date(0), (1970, 1, 1));
+        assert_eq!(civil_date(11016), (2000, 2, 29));
+    }
+}
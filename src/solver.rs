@@ -0,0 +1,999 @@
+//! Logical deductions from what a player can see on a [`Board`].
+//!
+//! The solver only ever looks at open squares, their numbers, flags and the
+//! total number of mines; it never reads whether a closed square has a mine.
+//! Flags are taken to be correct, and a square is taken to hold at most one
+//! mine; on boards whose squares can hold more, nothing is deduced.
+//!
+//! Deductions are tried from the cheapest rule up:
+//!
+//! 1. a single number whose mines are all flagged, or whose closed
+//!    neighbours must all be mines,
+//! 2. a number whose closed neighbours are a subset of another number's,
+//!    which decides the squares only the larger one touches,
+//! 3. every arrangement of mines on each connected part of the frontier,
+//!    weighed against the mines left on the whole board.
+//!
+//! The same enumeration gives the exact chance of every closed square being
+//! a mine, see [`probabilities`].
+
+use std::collections::HashMap;
+use std::ops::Index;
+
+use crate::{ Board, MineBoard };
+
+/// How many search steps the enumeration of a single frontier component may
+/// take before it is given up on.
+const ENUMERATION_BUDGET: usize = 1 << 20;
+
+/// Closed squares that are certainly safe or certainly mines
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub struct Deductions {
+    pub safe: Vec<(usize, usize)>,
+    pub mines: Vec<(usize, usize)>,
+}
+impl Deductions {
+    pub fn is_empty(&self) -> bool {
+        self.safe.is_empty() && self.mines.is_empty()
+    }
+}
+
+/// What a player knows about a single square
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Square {
+    Unknown,
+    Flag,
+    Open(u8),
+}
+
+/// Says that exactly `mines` of the unknown `cells` around `source` are mines
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct Constraint {
+    source: usize,
+    cells: Vec<usize>,
+    mines: usize,
+}
+
+/// The visible part of a board, with squares addressed by `y*width + x`
+struct View<'a> {
+    board: &'a Board,
+    squares: Vec<Square>,
+    /// Mines not accounted for by flags
+    remaining: usize,
+}
+impl<'a> View<'a> {
+    /// Reads the visible state of `board`. Returns `None` if the flags
+    /// contradict the numbers or the mine count, or if squares can hold more
+    /// than one mine.
+    fn new(board: &'a Board) -> Option<Self> {
+        if board.mines_per_square() > 1 {
+            return None;
+        }
+        let mut squares = Vec::with_capacity(board.width() * board.height());
+        for y in 0..board.height() {
+            for x in 0..board.width() {
+                let cell = board[(x, y)];
+                squares.push(match (cell.open, cell.is_flagged()) {
+                    (true, _) => Square::Open(cell.num),
+                    (false, true) => Square::Flag,
+                    (false, false) => Square::Unknown,
+                });
+            }
+        }
+        let flags = squares.iter().filter(|s| **s == Square::Flag).count();
+        Some(Self {
+            board,
+            squares,
+            remaining: board.num_mines().checked_sub(flags)?,
+        })
+    }
+
+    fn pos(&self, i: usize) -> (usize, usize) {
+        (i % self.board.width(), i / self.board.width())
+    }
+
+    fn idx(&self, (x, y): (usize, usize)) -> usize {
+        y * self.board.width() + x
+    }
+
+    fn neighbors(&self, i: usize) -> impl Iterator<Item=usize> + '_ {
+        self.board.get_neighborhood(self.pos(i)).map(|p| self.idx(p))
+    }
+
+    /// One constraint for every open number with unknown neighbours.
+    fn constraints(&self) -> Option<Vec<Constraint>> {
+        let mut constraints = vec![];
+        for (source, square) in self.squares.iter().enumerate() {
+            let Square::Open(num) = *square else { continue };
+            let mut flags = 0;
+            let mut cells = vec![];
+            for n in self.neighbors(source) {
+                match self.squares[n] {
+                    Square::Flag => flags += 1,
+                    Square::Unknown => cells.push(n),
+                    Square::Open(_) => {},
+                }
+            }
+            let mines = (num as usize).checked_sub(flags)?;
+            if mines > cells.len() {
+                return None;
+            }
+            if !cells.is_empty() {
+                cells.sort();
+                constraints.push(Constraint { source, cells, mines });
+            }
+        }
+        Some(constraints)
+    }
+
+    fn unknowns(&self) -> impl Iterator<Item=usize> + '_ {
+        (0..self.squares.len()).filter(|&i| self.squares[i] == Square::Unknown)
+    }
+}
+
+/// A square a rule decided, and the numbers it was decided from
+#[derive(Debug, Clone)]
+struct Finding {
+    mine: bool,
+    reasons: Vec<usize>,
+}
+
+/// Everything a rule decided, by square
+struct Findings(Vec<Option<Finding>>);
+impl Findings {
+    fn new(view: &View) -> Self {
+        Self(vec![None; view.squares.len()])
+    }
+
+    /// Records that square `i` is (or isn't) a mine because of `reasons`,
+    /// unless it was already decided.
+    fn mark(&mut self, i: usize, mine: bool, reasons: &[usize]) {
+        self.0[i].get_or_insert_with(|| Finding { mine, reasons: reasons.to_vec() });
+    }
+
+    fn is_empty(&self) -> bool {
+        self.0.iter().all(Option::is_none)
+    }
+
+    fn deductions(&self, view: &View) -> Deductions {
+        let marked = |mine: bool| (0..self.0.len())
+            .filter(|&i| matches!(&self.0[i], Some(f) if f.mine == mine))
+            .map(|i| view.pos(i))
+            .collect();
+        Deductions {
+            safe: marked(false),
+            mines: marked(true),
+        }
+    }
+}
+
+/// Rule 1: numbers that decide all of their closed neighbours on their own.
+fn single(view: &View, constraints: &[Constraint]) -> Findings {
+    let mut findings = Findings::new(view);
+    for constraint in constraints {
+        let mine = match constraint.mines {
+            0 => false,
+            n if n == constraint.cells.len() => true,
+            _ => continue,
+        };
+        for &i in &constraint.cells {
+            findings.mark(i, mine, &[constraint.source]);
+        }
+    }
+    findings
+}
+
+/// Rule 2: if one number's closed neighbours are a subset of another's, the
+/// difference in their counts is the number of mines on the squares only the
+/// larger one touches.
+fn subset(view: &View, constraints: &[Constraint]) -> Findings {
+    let mut by_cell: HashMap<usize, Vec<usize>> = HashMap::new();
+    for (c, constraint) in constraints.iter().enumerate() {
+        for &i in &constraint.cells {
+            by_cell.entry(i).or_default().push(c);
+        }
+    }
+
+    let mut findings = Findings::new(view);
+    for small in constraints {
+        // every superset shares the first cell of the subset
+        for &b in &by_cell[&small.cells[0]] {
+            let big = &constraints[b];
+            if big.cells.len() <= small.cells.len()
+                || !small.cells.iter().all(|i| big.cells.binary_search(i).is_ok())
+            {
+                continue;
+            }
+            let rest: Vec<_> = big.cells.iter()
+                .filter(|i| small.cells.binary_search(i).is_err())
+                .collect();
+            let Some(left) = big.mines.checked_sub(small.mines) else { continue };
+            let mine = match left {
+                0 => false,
+                n if n == rest.len() => true,
+                _ => continue,
+            };
+            for &&i in &rest {
+                findings.mark(i, mine, &[small.source, big.source]);
+            }
+        }
+    }
+    findings
+}
+
+/// Every arrangement of mines on one connected part of the frontier
+struct Component {
+    /// `solutions[k]` is the number of arrangements with `k` mines
+    solutions: Vec<f64>,
+    /// `cell_solutions[k][c]` is how many of those put a mine on the `c`th
+    /// square of the component
+    cell_solutions: Vec<Vec<f64>>,
+}
+impl Component {
+    /// Enumerates the arrangements of `cells` satisfying `constraints`.
+    /// Returns `None` if that takes more than [`ENUMERATION_BUDGET`] steps.
+    fn enumerate(cells: &[usize], constraints: &[&Constraint]) -> Option<Self> {
+        let local: HashMap<usize, usize> = cells.iter()
+            .enumerate()
+            .map(|(c, &i)| (i, c))
+            .collect();
+        let mut by_cell = vec![vec![]; cells.len()];
+        for (k, constraint) in constraints.iter().enumerate() {
+            for i in &constraint.cells {
+                by_cell[local[i]].push(k);
+            }
+        }
+
+        let mut search = Search {
+            by_cell,
+            needed: constraints.iter().map(|c| c.mines as isize).collect(),
+            open: constraints.iter().map(|c| c.cells.len()).collect(),
+            assignment: vec![false; cells.len()],
+            solutions: vec![0.0; cells.len() + 1],
+            cell_solutions: vec![vec![0.0; cells.len()]; cells.len() + 1],
+            budget: ENUMERATION_BUDGET,
+        };
+        if !search.run(0, 0) {
+            return None;
+        }
+
+        Some(Self {
+            solutions: search.solutions,
+            cell_solutions: search.cell_solutions,
+        })
+    }
+
+    fn min_mines(&self) -> usize {
+        self.solutions.iter().position(|&n| n > 0.0).unwrap_or(0)
+    }
+
+    fn max_mines(&self) -> usize {
+        self.solutions.iter().rposition(|&n| n > 0.0).unwrap_or(0)
+    }
+}
+
+/// Backtracking state for [`Component::enumerate`]
+struct Search {
+    /// The constraints each cell is part of
+    by_cell: Vec<Vec<usize>>,
+    /// Mines each constraint still needs
+    needed: Vec<isize>,
+    /// Unassigned cells left in each constraint
+    open: Vec<usize>,
+    assignment: Vec<bool>,
+    solutions: Vec<f64>,
+    cell_solutions: Vec<Vec<f64>>,
+    budget: usize,
+}
+impl Search {
+    /// Tries both values for cell `c` onwards. Returns `false` once the
+    /// budget runs out.
+    fn run(&mut self, c: usize, mines: usize) -> bool {
+        if self.budget == 0 {
+            return false;
+        }
+        self.budget -= 1;
+
+        if c == self.assignment.len() {
+            self.solutions[mines] += 1.0;
+            for (c, &mine) in self.assignment.iter().enumerate() {
+                if mine {
+                    self.cell_solutions[mines][c] += 1.0;
+                }
+            }
+            return true;
+        }
+
+        for mine in [false, true] {
+            if self.assign(c, mine) {
+                self.assignment[c] = mine;
+                let finished = self.run(c + 1, mines + mine as usize);
+                self.assignment[c] = false;
+                self.unassign(c, mine);
+                if !finished {
+                    return false;
+                }
+            } else {
+                self.unassign(c, mine);
+            }
+        }
+        true
+    }
+
+    /// Assigns cell `c`, returning whether every constraint can still be met.
+    fn assign(&mut self, c: usize, mine: bool) -> bool {
+        let mut ok = true;
+        for &k in &self.by_cell[c] {
+            self.open[k] -= 1;
+            self.needed[k] -= mine as isize;
+            if self.needed[k] < 0 || self.needed[k] > self.open[k] as isize {
+                ok = false;
+            }
+        }
+        ok
+    }
+
+    fn unassign(&mut self, c: usize, mine: bool) {
+        for &k in &self.by_cell[c] {
+            self.open[k] += 1;
+            if mine {
+                self.needed[k] += 1;
+            }
+        }
+    }
+}
+
+/// Splits the frontier into groups of squares linked by shared numbers.
+fn components(view: &View, constraints: &[Constraint]) -> Vec<(Vec<usize>, Vec<usize>)> {
+    let mut parent: Vec<usize> = (0..constraints.len()).collect();
+    fn find(parent: &mut [usize], mut k: usize) -> usize {
+        while parent[k] != k {
+            parent[k] = parent[parent[k]];
+            k = parent[k];
+        }
+        k
+    }
+
+    let mut owner: HashMap<usize, usize> = HashMap::new();
+    for (k, constraint) in constraints.iter().enumerate() {
+        for &i in &constraint.cells {
+            match owner.get(&i) {
+                Some(&other) => {
+                    let (a, b) = (find(&mut parent, k), find(&mut parent, other));
+                    parent[a] = b;
+                },
+                None => { owner.insert(i, k); },
+            }
+        }
+    }
+
+    let mut groups: HashMap<usize, (Vec<usize>, Vec<usize>)> = HashMap::new();
+    for k in 0..constraints.len() {
+        let root = find(&mut parent, k);
+        groups.entry(root).or_default().1.push(k);
+    }
+    for i in view.unknowns() {
+        if let Some(&k) = owner.get(&i) {
+            let root = find(&mut parent, k);
+            groups.get_mut(&root).unwrap().0.push(i);
+        }
+    }
+
+    let mut groups: Vec<_> = groups.into_values().collect();
+    groups.sort();
+    groups
+}
+
+/// The frontier enumerated against the global mine count
+struct Frontier {
+    /// The unknown squares in each component
+    cells: Vec<Vec<usize>>,
+    /// The numbers around each component
+    sources: Vec<Vec<usize>>,
+    /// The arrangements of each component, unless there were too many
+    components: Vec<Option<Component>>,
+    /// Unknown squares not next to any number
+    floating: Vec<usize>,
+}
+impl Frontier {
+    fn new(view: &View, constraints: &[Constraint]) -> Self {
+        let mut on_frontier = vec![false; view.squares.len()];
+        let mut cells = vec![];
+        let mut sources = vec![];
+        let components = components(view, constraints)
+            .into_iter()
+            .map(|(component, ks)| {
+                for &i in &component {
+                    on_frontier[i] = true;
+                }
+                let constraints: Vec<_> = ks.iter().map(|&k| &constraints[k]).collect();
+                let enumerated = Component::enumerate(&component, &constraints);
+                cells.push(component);
+                sources.push(constraints.iter().map(|c| c.source).collect());
+                enumerated
+            })
+            .collect();
+        let floating = view.unknowns().filter(|&i| !on_frontier[i]).collect();
+
+        Self { cells, sources, components, floating }
+    }
+
+    /// `reachable[t]` says whether the components other than `skip` can hold
+    /// `t` mines between them. Components that could not be enumerated are
+    /// taken to allow any amount.
+    fn reachable(&self, skip: Option<usize>) -> Vec<bool> {
+        let total: usize = self.cells.iter().map(Vec::len).sum();
+        let mut reachable = vec![false; total + 1];
+        reachable[0] = true;
+        for c in 0..self.cells.len() {
+            if Some(c) == skip {
+                continue;
+            }
+            let size = self.cells[c].len();
+            let mut next = vec![false; total + 1];
+            for t in (0..=total).filter(|&t| reachable[t]) {
+                for k in 0..=size {
+                    let possible = match &self.components[c] {
+                        Some(component) => component.solutions[k] > 0.0,
+                        None => true,
+                    };
+                    if possible && t + k <= total {
+                        next[t + k] = true;
+                    }
+                }
+            }
+            reachable = next;
+        }
+        reachable
+    }
+}
+
+/// Rule 3: enumerates every arrangement of each frontier component, keeping
+/// only mine counts that fit the mines left on the board.
+fn enumerate(view: &View, constraints: &[Constraint]) -> Findings {
+    let frontier = Frontier::new(view, constraints);
+    let floating = frontier.floating.len();
+    // whether a frontier total of `t` mines leaves a valid amount elsewhere
+    let fits = |t: usize| t <= view.remaining && view.remaining - t <= floating;
+
+    let mut findings = Findings::new(view);
+
+    for (c, component) in frontier.components.iter().enumerate() {
+        let Some(component) = component else { continue };
+        let others = frontier.reachable(Some(c));
+        let feasible: Vec<usize> = (component.min_mines()..=component.max_mines())
+            .filter(|&k| component.solutions[k] > 0.0)
+            .filter(|&k| (0..others.len()).any(|t| others[t] && fits(k + t)))
+            .collect();
+        if feasible.is_empty() {
+            return Findings::new(view);
+        }
+        for (n, &i) in frontier.cells[c].iter().enumerate() {
+            if feasible.iter().all(|&k| component.cell_solutions[k][n] == 0.0) {
+                findings.mark(i, false, &frontier.sources[c]);
+            }
+            if feasible.iter().all(|&k| component.cell_solutions[k][n] == component.solutions[k]) {
+                findings.mark(i, true, &frontier.sources[c]);
+            }
+        }
+    }
+
+    if floating > 0 {
+        let all = frontier.reachable(None);
+        let totals: Vec<usize> = (0..all.len())
+            .filter(|&t| all[t] && fits(t))
+            .collect();
+        let exact = |left: usize| !totals.is_empty()
+            && totals.iter().all(|&t| view.remaining - t == left);
+        let reasons: Vec<usize> = frontier.sources.concat();
+        if exact(0) {
+            for &i in &frontier.floating {
+                findings.mark(i, false, &reasons);
+            }
+        } else if exact(floating) {
+            for &i in &frontier.floating {
+                findings.mark(i, true, &reasons);
+            }
+        }
+    }
+
+    findings
+}
+
+type Rule = fn(&View, &[Constraint]) -> Findings;
+
+/// The rules in the order they are tried
+const RULES: [Rule; 3] = [single, subset, enumerate];
+
+/// Runs the rules on `board` until one of them decides anything.
+fn find<T>(board: &Board, f: impl FnOnce(&View, Findings) -> T) -> Option<T> {
+    let view = View::new(board)?;
+    let constraints = view.constraints()?;
+    RULES.iter()
+        .map(|rule| rule(&view, &constraints))
+        .find(|findings| !findings.is_empty())
+        .map(|findings| f(&view, findings))
+}
+
+/// Finds closed squares that are certainly safe or certainly mines, using
+/// the simplest rule that decides anything.
+pub fn deduce(board: &Board) -> Deductions {
+    find(board, |view, findings| findings.deductions(view)).unwrap_or_default()
+}
+
+/// Plays out deductions on `board`, opening safe squares and flagging mines
+/// until nothing more can be deduced. Returns whether the board is cleared.
+pub fn solve(board: &mut Board) -> bool {
+    loop {
+        let deductions = deduce(board);
+        if deductions.is_empty() {
+            return board.is_won();
+        }
+        for p in deductions.mines {
+            board[p].flags = 1;
+        }
+        for p in deductions.safe {
+            if board.open(p) {
+                return false;
+            }
+        }
+    }
+}
+
+/// What a hint says about its square
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum HintKind {
+    /// The square is certainly safe
+    Safe,
+    /// The square is certainly a mine
+    Mine,
+    /// Nothing is certain; this is the square least likely to be a mine
+    Guess(f64),
+}
+
+/// A single move suggested from what is visible on a board
+#[derive(Debug, Clone, PartialEq)]
+pub struct Hint {
+    pub square: (usize, usize),
+    pub kind: HintKind,
+    /// The numbers the hint follows from
+    pub reasons: Vec<(usize, usize)>,
+}
+
+/// Suggests a square that is certainly safe, or else one that is certainly
+/// a mine, along with the numbers that show it. Falls back to the square
+/// least likely to be a mine when nothing can be deduced.
+pub fn hint(board: &Board) -> Option<Hint> {
+    let deduced = find(board, |view, findings| {
+        let (i, finding) = findings.0.iter()
+            .enumerate()
+            .filter_map(|(i, f)| Some((i, f.as_ref()?)))
+            .min_by_key(|(_, f)| f.mine)?;
+        Some(Hint {
+            square: view.pos(i),
+            kind: if finding.mine { HintKind::Mine } else { HintKind::Safe },
+            reasons: finding.reasons.iter().map(|&r| view.pos(r)).collect(),
+        })
+    }).flatten();
+    if deduced.is_some() {
+        return deduced;
+    }
+
+    let probabilities = probabilities(board)?;
+    (0..board.height())
+        .flat_map(|y| (0..board.width()).map(move |x| (x, y)))
+        .filter(|&p| !board[p].open && !board[p].is_flagged())
+        .min_by(|&a, &b| probabilities[a].total_cmp(&probabilities[b]))
+        .map(|square| Hint {
+            square,
+            kind: HintKind::Guess(probabilities[square]),
+            reasons: vec![],
+        })
+}
+
+/// The chance of a mine on every square of a board
+#[derive(Debug, Clone)]
+pub struct Probabilities {
+    width: usize,
+    height: usize,
+    values: Vec<f64>,
+}
+impl Probabilities {
+    pub fn width(&self) -> usize { self.width }
+
+    pub fn height(&self) -> usize { self.height }
+}
+impl Index<(usize, usize)> for Probabilities {
+    type Output = f64;
+    fn index(&self, (x, y): (usize, usize)) -> &Self::Output {
+        &self.values[y*self.width + x]
+    }
+}
+
+/// `ln(n!)` for every `n` up to `max`
+fn ln_factorials(max: usize) -> Vec<f64> {
+    let mut ln = vec![0.0; max + 1];
+    for n in 1..=max {
+        ln[n] = ln[n - 1] + (n as f64).ln();
+    }
+    ln
+}
+
+/// Convolves the mine counts of every component but `skip`: `result[t]` is
+/// the (scaled) number of ways they can hold `t` mines between them.
+fn convolve(components: &[Vec<f64>], skip: Option<usize>) -> Vec<f64> {
+    let mut result = vec![1.0];
+    for (c, solutions) in components.iter().enumerate() {
+        if Some(c) == skip {
+            continue;
+        }
+        let mut next = vec![0.0; result.len() + solutions.len() - 1];
+        for (t, &a) in result.iter().enumerate() {
+            for (k, &b) in solutions.iter().enumerate() {
+                next[t + k] += a * b;
+            }
+        }
+        result = next;
+    }
+    result
+}
+
+/// Computes the exact chance of a mine on every closed square from what a
+/// player can see, taking the total number of mines into account.
+///
+/// Open squares are 0 and flagged squares 1. Frontier components too large
+/// to enumerate are treated as if they had no numbers around them, which
+/// makes their squares approximate. Returns `None` if the flags contradict
+/// the numbers.
+pub fn probabilities(board: &Board) -> Option<Probabilities> {
+    let view = View::new(board)?;
+    let constraints = view.constraints()?;
+    let frontier = Frontier::new(&view, &constraints);
+
+    let mut values: Vec<f64> = view.squares.iter()
+        .map(|s| if *s == Square::Flag { 1.0 } else { 0.0 })
+        .collect();
+
+    // scale each component so its largest count is 1, which cancels out
+    // when dividing by the total weight but keeps the products in range
+    let mut floating = frontier.floating.clone();
+    let mut solutions = vec![];
+    let mut cell_solutions = vec![];
+    let mut cells = vec![];
+    for (component, component_cells) in frontier.components.iter().zip(&frontier.cells) {
+        match component {
+            Some(component) => {
+                let scale = component.solutions.iter().cloned().fold(0.0, f64::max);
+                if scale == 0.0 {
+                    return None;
+                }
+                solutions.push(component.solutions.iter().map(|n| n / scale).collect());
+                cell_solutions.push(component.cell_solutions.iter()
+                    .map(|k| k.iter().map(|n| n / scale).collect::<Vec<_>>())
+                    .collect::<Vec<_>>());
+                cells.push(component_cells);
+            },
+            None => floating.extend(component_cells),
+        }
+    }
+
+    // `weight(m)` is proportional to the ways of putting `m` mines on the
+    // floating squares
+    let remaining = view.remaining;
+    let ln = ln_factorials(floating.len());
+    let ln_choose = |m: usize| ln[floating.len()] - ln[m] - ln[floating.len() - m];
+    let peak = ln_choose(remaining.min(floating.len()).min(floating.len() / 2));
+    let weight = |frontier_mines: usize| match remaining.checked_sub(frontier_mines) {
+        Some(m) if m <= floating.len() => (ln_choose(m) - peak).exp(),
+        _ => 0.0,
+    };
+
+    let all = convolve(&solutions, None);
+    let total: f64 = all.iter()
+        .enumerate()
+        .map(|(t, n)| n * weight(t))
+        .sum();
+    if total == 0.0 {
+        return None;
+    }
+
+    for c in 0..cells.len() {
+        let others = convolve(&solutions, Some(c));
+        let factors: Vec<f64> = (0..solutions[c].len())
+            .map(|k| others.iter()
+                .enumerate()
+                .map(|(t, n)| n * weight(k + t))
+                .sum())
+            .collect();
+        for (n, &i) in cells[c].iter().enumerate() {
+            values[i] = factors.iter()
+                .enumerate()
+                .map(|(k, f)| cell_solutions[c][k][n] * f)
+                .sum::<f64>() / total;
+        }
+    }
+
+    if !floating.is_empty() {
+        let expected: f64 = all.iter()
+            .enumerate()
+            .map(|(t, n)| n * weight(t) * remaining.saturating_sub(t) as f64)
+            .sum::<f64>() / total;
+        for &i in &floating {
+            values[i] = expected / floating.len() as f64;
+        }
+    }
+
+    Some(Probabilities {
+        width: board.width(),
+        height: board.height(),
+        values,
+    })
+}
+
+#[cfg(test)]
+pub mod test {
+    use super::*;
+
+    /// Builds a board with the given mines where only `open` is visible.
+    fn position(
+        (width, height): (usize, usize),
+        mines: &[(usize, usize)],
+        open: &[(usize, usize)],
+        flags: &[(usize, usize)],
+    ) -> Board {
+        let mut board = Board::with_mines(width, height, mines);
+        for &p in open {
+            board[p].open = true;
+        }
+        for &p in flags {
+            board[p].flags = 1;
+        }
+        board
+    }
+
+    #[test]
+    pub fn single_safe() {
+        let board = position((3, 2), &[(0, 0)], &[(1, 0)], &[(0, 0)]);
+        assert_eq!(deduce(&board), Deductions {
+            safe: vec![(2, 0), (0, 1), (1, 1), (2, 1)],
+            mines: vec![],
+        });
+    }
+
+    #[test]
+    pub fn single_mine() {
+        let board = position((2, 2), &[(0, 0)], &[(1, 0), (0, 1), (1, 1)], &[]);
+        assert_eq!(deduce(&board), Deductions {
+            safe: vec![],
+            mines: vec![(0, 0)],
+        });
+    }
+
+    #[test]
+    pub fn subset_safe() {
+        // . * .
+        // 1 1 1
+        let board = position((3, 2), &[(1, 0)], &[(0, 1), (1, 1), (2, 1)], &[]);
+        assert_eq!(deduce(&board), Deductions {
+            safe: vec![(0, 0), (2, 0)],
+            mines: vec![],
+        });
+    }
+
+    #[test]
+    pub fn subset_mine() {
+        // * . *
+        // 1 2 1
+        let board = position((3, 2), &[(0, 0), (2, 0)], &[(0, 1), (1, 1), (2, 1)], &[]);
+        assert_eq!(deduce(&board), Deductions {
+            safe: vec![],
+            mines: vec![(0, 0), (2, 0)],
+        });
+    }
+
+    #[test]
+    pub fn enumerate_global_count() {
+        // both numbers need their own mine, which uses up every mine on the
+        // board and leaves the square between them safe
+        // * 1 . . . 1 *
+        let board = position((7, 1), &[(0, 0), (6, 0)], &[(1, 0), (5, 0)], &[]);
+        assert_eq!(deduce(&board), Deductions {
+            safe: vec![(3, 0)],
+            mines: vec![],
+        });
+    }
+
+    #[test]
+    pub fn enumerate_frontier() {
+        // with only two mines on the board, the middle 2 must take both and
+        // the outer 2s can only share them where all three numbers overlap
+        // . . * . .
+        // . 2 2 2 .
+        // . . * . .
+        let board = position(
+            (5, 3),
+            &[(2, 0), (2, 2)],
+            &[(1, 1), (2, 1), (3, 1)],
+            &[],
+        );
+        assert_eq!(deduce(&board), Deductions {
+            safe: vec![
+                (0, 0), (1, 0), (3, 0), (4, 0),
+                (0, 1), (4, 1),
+                (0, 2), (1, 2), (3, 2), (4, 2),
+            ],
+            mines: vec![(2, 0), (2, 2)],
+        });
+    }
+
+    #[test]
+    pub fn no_deduction() {
+        let board = position((3, 3), &[(0, 0)], &[(1, 1)], &[]);
+        assert!(deduce(&board).is_empty());
+    }
+
+    #[test]
+    pub fn hidden_mines_are_not_read() {
+        // same numbers, different hidden layout
+        let a = position((7, 1), &[(0, 0), (6, 0)], &[(1, 0), (5, 0)], &[]);
+        let b = position((7, 1), &[(2, 0), (4, 0)], &[(1, 0), (5, 0)], &[]);
+        assert_eq!(deduce(&a), deduce(&b));
+
+        let a = position((3, 3), &[(0, 0)], &[(1, 1)], &[]);
+        let b = position((3, 3), &[(2, 2)], &[(1, 1)], &[]);
+        assert_eq!(deduce(&a), deduce(&b));
+    }
+
+    #[test]
+    pub fn contradicting_flags() {
+        let board = position((3, 1), &[(2, 0)], &[(1, 0)], &[(0, 0), (2, 0)]);
+        assert!(deduce(&board).is_empty());
+    }
+
+    #[test]
+    pub fn solve_board() {
+        let mut board = Board::with_mines(7, 1, &[(0, 0), (6, 0)]);
+        board.open((1, 0));
+        board.open((5, 0));
+        assert!(solve(&mut board));
+        assert!(board[(0, 0)].is_flagged() && board[(6, 0)].is_flagged());
+    }
+
+    #[test]
+    pub fn solve_wrapped_board() {
+        // the mine is next to both ends once the row wraps around
+        let mut board = Board::with_mines(5, 1, &[(0, 0)]);
+        board.set_wrap(crate::Wrap { horizontal: true, vertical: false });
+        assert_eq!(board[(4, 0)].num, 1);
+        board.open((2, 0));
+        assert!(solve(&mut board));
+        assert!(board[(0, 0)].is_flagged() && board.is_won());
+    }
+
+    /// Counts every layout consistent with what is visible on `board`.
+    fn brute_force(board: &Board) -> Vec<f64> {
+        let squares: Vec<(usize, usize)> = (0..board.height())
+            .flat_map(|y| (0..board.width()).map(move |x| (x, y)))
+            .collect();
+        let unknown: Vec<usize> = (0..squares.len())
+            .filter(|&i| !board[squares[i]].open && !board[squares[i]].is_flagged())
+            .collect();
+        let flags = squares.iter().filter(|&&p| board[p].is_flagged()).count();
+        let num_mines = board.num_mines();
+
+        let mut counts = vec![0.0; squares.len()];
+        let mut total = 0.0;
+        for layout in 0u32..1 << unknown.len() {
+            if layout.count_ones() as usize + flags != num_mines {
+                continue;
+            }
+            let mine = |p: (usize, usize)| {
+                let i = squares.iter().position(|&q| q == p).unwrap();
+                board[p].is_flagged() || unknown.iter()
+                    .position(|&u| u == i)
+                    .is_some_and(|u| layout & (1 << u) != 0)
+            };
+            let consistent = squares.iter()
+                .filter(|&&p| board[p].open)
+                .all(|&p| board.get_neighborhood(p).filter(|&q| mine(q)).count()
+                    == board[p].num as usize);
+            if consistent {
+                total += 1.0;
+                for (u, &i) in unknown.iter().enumerate() {
+                    if layout & (1 << u) != 0 {
+                        counts[i] += 1.0;
+                    }
+                }
+            }
+        }
+        for i in 0..squares.len() {
+            counts[i] = if board[squares[i]].is_flagged() { 1.0 } else { counts[i] / total };
+        }
+        counts
+    }
+
+    fn assert_close(board: &Board, expected: &[f64]) {
+        let probabilities = probabilities(board).unwrap();
+        for y in 0..board.height() {
+            for x in 0..board.width() {
+                let (got, want) = (probabilities[(x, y)], expected[y * board.width() + x]);
+                assert!((got - want).abs() < 1e-9, "{:?}: {} != {}", (x, y), got, want);
+            }
+        }
+    }
+
+    #[test]
+    pub fn probabilities_local() {
+        let board = position((3, 3), &[(0, 0)], &[(1, 1)], &[]);
+        let p = probabilities(&board).unwrap();
+        assert_eq!(p[(1, 1)], 0.0);
+        assert!((p[(0, 0)] - 0.125).abs() < 1e-9);
+        assert!((p[(2, 2)] - 0.125).abs() < 1e-9);
+    }
+
+    #[test]
+    pub fn probabilities_global() {
+        // one mine around the 1, the other among the six squares on the right
+        let board = position((5, 3), &[(0, 0), (4, 1)], &[(1, 1)], &[]);
+        let p = probabilities(&board).unwrap();
+        assert!((p[(0, 0)] - 1.0 / 8.0).abs() < 1e-9);
+        assert!((p[(4, 1)] - 1.0 / 6.0).abs() < 1e-9);
+
+        let board = position((7, 1), &[(0, 0), (6, 0)], &[(1, 0), (5, 0)], &[]);
+        assert_close(&board, &[0.5, 0.0, 0.5, 0.0, 0.5, 0.0, 0.5]);
+    }
+
+    #[test]
+    pub fn probabilities_brute_force() {
+        for seed in 0..20 {
+            let mut board = Board::new(5, 4);
+            board.generate_seeded(5, (2, 2), seed);
+            board.open((2, 2));
+            assert_close(&board, &brute_force(&board));
+
+            if let Some(&p) = deduce(&board).mines.first() {
+                board[p].flags = 1;
+                assert_close(&board, &brute_force(&board));
+            }
+        }
+    }
+
+    #[test]
+    pub fn probabilities_contradiction() {
+        let board = position((3, 1), &[(2, 0)], &[(1, 0)], &[(0, 0), (2, 0)]);
+        assert!(probabilities(&board).is_none());
+    }
+
+    #[test]
+    pub fn hint_deduced() {
+        let board = position((3, 2), &[(0, 0)], &[(1, 0)], &[(0, 0)]);
+        assert_eq!(hint(&board), Some(Hint {
+            square: (2, 0),
+            kind: HintKind::Safe,
+            reasons: vec![(1, 0)],
+        }));
+
+        // * . *
+        // 1 2 1
+        let board = position((3, 2), &[(0, 0), (2, 0)], &[(0, 1), (1, 1), (2, 1)], &[]);
+        let hint = hint(&board).unwrap();
+        assert_eq!(hint.kind, HintKind::Mine);
+        assert!(hint.reasons.contains(&(1, 1)));
+        assert_eq!(hint.reasons.len(), 2);
+    }
+
+    #[test]
+    pub fn hint_guess() {
+        // the corners around the 1 are as likely as any other square
+        let board = position((3, 3), &[(0, 0)], &[(1, 1)], &[]);
+        assert_eq!(hint(&board), Some(Hint {
+            square: (0, 0),
+            kind: HintKind::Guess(0.125),
+            reasons: vec![],
+        }));
+
+        // one mine around the 1 and one among the six squares on the right
+        let board = position((5, 3), &[(0, 0), (4, 1)], &[(1, 1)], &[]);
+        assert_eq!(hint(&board).unwrap().square, (0, 0));
+    }
+}
@@ -0,0 +1,54 @@
+use std::time::{ Duration, Instant };
+
+/// A stopwatch for timing a game
+#[derive(Debug, Clone, Copy, Default)]
+pub struct Timer {
+    started: Option<Instant>,
+    stopped: Option<Duration>,
+    /// Time counted before starting, for games picked up part way through
+    before: Duration,
+}
+impl Timer {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Starts timing, unless the timer is already running or stopped.
+    pub fn start(&mut self) {
+        if self.started.is_none() {
+            self.started = Some(Instant::now());
+        }
+    }
+
+    /// Starts timing as though `elapsed` had already gone by.
+    pub fn start_from(&mut self, elapsed: Duration) {
+        *self = Self::default();
+        self.before = elapsed;
+        self.start();
+    }
+
+    /// Freezes the timer at the time elapsed so far.
+    pub fn stop(&mut self) {
+        if self.stopped.is_none() {
+            self.stopped = Some(self.elapsed());
+        }
+    }
+
+    /// Stops the timer and sets it back to zero.
+    pub fn reset(&mut self) {
+        *self = Self::default();
+    }
+
+    pub fn is_running(&self) -> bool {
+        self.started.is_some() && self.stopped.is_none()
+    }
+
+    /// The time between starting and stopping, or now if still running.
+    pub fn elapsed(&self) -> Duration {
+        match (self.started, self.stopped) {
+            (_, Some(elapsed)) => elapsed,
+            (Some(started), None) => self.before + started.elapsed(),
+            (None, None) => Duration::ZERO,
+        }
+    }
+}